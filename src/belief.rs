@@ -0,0 +1,609 @@
+use ndarray::Array2;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{BeliefModel, InitialBeliefs, MemoryConfig, Precision};
+use crate::grid::{Grid, Layout};
+use crate::map::Biome;
+use crate::person::ResourceProbability;
+use crate::resource::Resource;
+
+/// Pseudo-count every resource starts out with in a `Dirichlet` belief.
+pub const PRIOR_COUNT: f64 = 1.0;
+
+/// Total pseudo-count of a `Dirichlet` belief that's seen nothing yet.
+pub const PRIOR_CONCENTRATION: f64 =
+  PRIOR_COUNT * Resource::variant_count() as f64;
+
+/// What someone believes about the resource in one cell of the map.
+///
+/// Beliefs are shared and drawn as plain distributions, so people holding
+/// different kinds of belief can still talk to each other.
+pub trait Belief {
+  fn from_distribution(distribution: &ResourceProbability) -> Self
+  where
+    Self: Sized;
+
+  /// Updates the belief after sensing `sensed` with a sensor that's right
+  /// with probability `accuracy`.
+  fn observe(&mut self, sensed: Resource, accuracy: f64);
+
+  /// Updates the belief after seeing `resource` with a `certainty` that falls
+  /// off with distance.
+  fn perceive(&mut self, resource: Resource, certainty: f64) {
+    self.merge(
+      &ResourceProbability::probable(resource, certainty),
+      None,
+      certainty * certainty,
+    );
+  }
+
+  /// Moves the belief towards what someone else believes, further the more
+  /// they're trusted. `concentration` is how much evidence their belief rests
+  /// on, if they keep count.
+  fn merge(
+    &mut self,
+    other: &ResourceProbability,
+    concentration: Option<f64>,
+    trust: f64,
+  );
+
+  /// Forgets `percent` of the belief.
+  fn decay(&mut self, percent: f64);
+
+  /// Forgets `percent` of the belief, falling back on `prior` rather than
+  /// even.
+  fn decay_towards(&mut self, percent: f64, prior: &ResourceProbability);
+
+  fn most_likely(&self) -> Resource;
+
+  fn probability(&self, resource: Resource) -> f64;
+
+  /// How much evidence the belief rests on, for beliefs that keep count.
+  fn concentration(&self) -> Option<f64> {
+    None
+  }
+
+  /// How sure the belief is, from 0 for no idea to 1 for certain.
+  fn confidence(&self) -> f64 {
+    1.0 - self.entropy() / (Resource::variant_count() as f64).log2()
+  }
+
+  fn distribution(&self) -> ResourceProbability {
+    ResourceProbability::from_fn(|r| self.probability(r))
+  }
+
+  /// Shannon entropy, in bits.
+  fn entropy(&self) -> f64 {
+    -Resource::variants()
+      .iter()
+      .map(|r| self.probability(*r))
+      .filter(|p| *p > 0.0)
+      .map(|p| p * p.log2())
+      .sum::<f64>()
+  }
+}
+
+impl Belief for ResourceProbability {
+  fn from_distribution(distribution: &ResourceProbability) -> Self {
+    distribution.clone()
+  }
+
+  fn observe(&mut self, sensed: Resource, accuracy: f64) {
+    ResourceProbability::observe(self, sensed, accuracy);
+  }
+
+  fn merge(
+    &mut self,
+    other: &ResourceProbability,
+    _concentration: Option<f64>,
+    trust: f64,
+  ) {
+    self.adjust_towards(other, trust);
+  }
+
+  fn decay(&mut self, percent: f64) {
+    self.resdistribute(percent);
+  }
+
+  fn decay_towards(&mut self, percent: f64, prior: &ResourceProbability) {
+    self.fade_towards(prior, percent);
+  }
+
+  fn most_likely(&self) -> Resource {
+    self.plurality()
+  }
+
+  fn probability(&self, resource: Resource) -> f64 {
+    self.get(resource)
+  }
+
+  fn distribution(&self) -> ResourceProbability {
+    self.clone()
+  }
+
+  fn entropy(&self) -> f64 {
+    ResourceProbability::entropy(self)
+  }
+}
+
+/// Believes in a single resource, the last one seen or heard of, with a
+/// confidence that fades over time.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LastSeen {
+  resource: Resource,
+  /// How far the belief is from even, between 0 and 1.
+  confidence: f64,
+}
+
+impl LastSeen {
+  /// Confidence that puts probability `p` on the believed resource.
+  fn confidence(p: f64) -> f64 {
+    let even = 1.0 / Resource::variant_count() as f64;
+    ((p - even) / (1.0 - even)).max(0.0)
+  }
+}
+
+impl Belief for LastSeen {
+  fn from_distribution(distribution: &ResourceProbability) -> Self {
+    let resource = distribution.plurality();
+    Self {
+      resource,
+      confidence: Self::confidence(distribution.get(resource)),
+    }
+  }
+
+  fn observe(&mut self, sensed: Resource, accuracy: f64) {
+    self.resource = sensed;
+    self.confidence = Self::confidence(accuracy);
+  }
+
+  /// Takes on whatever was seen last, however uncertain, as `observe` does.
+  /// Cells out of sight, with no certainty at all, are left as they are.
+  fn perceive(&mut self, resource: Resource, certainty: f64) {
+    if certainty > 0.0 {
+      self.resource = resource;
+      self.confidence = certainty;
+    }
+  }
+
+  /// Takes on what the other believes most likely if, scaled by trust, they
+  /// believe it at least as confidently.
+  fn merge(
+    &mut self,
+    other: &ResourceProbability,
+    _concentration: Option<f64>,
+    trust: f64,
+  ) {
+    let other = Self::from_distribution(other);
+    if other.confidence * trust >= self.confidence {
+      self.resource = other.resource;
+      self.confidence = other.confidence * trust;
+    }
+  }
+
+  fn decay(&mut self, percent: f64) {
+    self.confidence -= self.confidence * percent;
+  }
+
+  /// Fades as a distribution would, taking on the prior's most likely
+  /// resource once the memory has faded below it.
+  fn decay_towards(&mut self, percent: f64, prior: &ResourceProbability) {
+    let mut distribution = self.distribution();
+    distribution.fade_towards(prior, percent);
+    *self = Self::from_distribution(&distribution);
+  }
+
+  fn most_likely(&self) -> Resource {
+    self.resource
+  }
+
+  fn probability(&self, resource: Resource) -> f64 {
+    ResourceProbability::probable(self.resource, self.confidence).get(resource)
+  }
+}
+
+/// Pseudo-counts of how often each resource has been seen, telling a belief
+/// that's seen nothing apart from one that's seen an even mix many times.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Dirichlet {
+  counts: [f64; Resource::variant_count()],
+}
+
+impl Dirichlet {
+  fn total(&self) -> f64 {
+    self.counts.iter().sum()
+  }
+}
+
+impl Belief for Dirichlet {
+  /// Takes on the distribution with as much weight as the prior.
+  fn from_distribution(distribution: &ResourceProbability) -> Self {
+    Self {
+      counts: ResourceProbability::from_fn(|r| {
+        distribution.get(r) * PRIOR_CONCENTRATION
+      })
+      .into_inner(),
+    }
+  }
+
+  /// Adds one count, split between resources by how likely each is to have
+  /// been what was sensed, and weighted by how much better than chance the
+  /// sensor is.
+  fn observe(&mut self, sensed: Resource, accuracy: f64) {
+    let chance = 1.0 / Resource::variant_count() as f64;
+    let weight = ((accuracy - chance) / (1.0 - chance)).max(0.0);
+    let mut posterior = self.distribution();
+    posterior.observe(sensed, accuracy);
+    for (i, count) in self.counts.iter_mut().enumerate() {
+      *count +=
+        weight * posterior.get(Resource::from_ordinal(i as i8).unwrap());
+    }
+  }
+
+  fn perceive(&mut self, resource: Resource, certainty: f64) {
+    let seen = ResourceProbability::probable(resource, certainty);
+    for (i, count) in self.counts.iter_mut().enumerate() {
+      *count += certainty
+        * certainty
+        * seen.get(Resource::from_ordinal(i as i8).unwrap());
+    }
+  }
+
+  /// Takes on, for each resource, the other's evidence beyond the prior
+  /// discounted by `trust` wherever it's more than what's already been seen,
+  /// so hearing the same thing over and over never adds up to more evidence
+  /// than the speaker has.
+  fn merge(
+    &mut self,
+    other: &ResourceProbability,
+    concentration: Option<f64>,
+    trust: f64,
+  ) {
+    let concentration = concentration.unwrap_or(PRIOR_CONCENTRATION);
+    for (i, count) in self.counts.iter_mut().enumerate() {
+      let heard =
+        other.get(Resource::from_ordinal(i as i8).unwrap()) * concentration;
+      let evidence = (*count - PRIOR_COUNT).max((heard - PRIOR_COUNT) * trust);
+      *count = PRIOR_COUNT + evidence;
+    }
+  }
+
+  /// Shrinks the counts towards the prior.
+  fn decay(&mut self, percent: f64) {
+    for count in self.counts.iter_mut() {
+      *count -= (*count - PRIOR_COUNT) * percent;
+    }
+  }
+
+  /// Shrinks the counts towards the prior's share of the prior concentration.
+  fn decay_towards(&mut self, percent: f64, prior: &ResourceProbability) {
+    for (i, count) in self.counts.iter_mut().enumerate() {
+      let target = prior.get(Resource::from_ordinal(i as i8).unwrap())
+        * PRIOR_CONCENTRATION;
+      *count -= (*count - target) * percent;
+    }
+  }
+
+  fn most_likely(&self) -> Resource {
+    let (i, _) = self
+      .counts
+      .iter()
+      .enumerate()
+      .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+      .unwrap();
+    Resource::from_ordinal(i as i8).unwrap()
+  }
+
+  fn probability(&self, resource: Resource) -> f64 {
+    self.counts[resource.ordinal() as usize] / self.total()
+  }
+
+  fn concentration(&self) -> Option<f64> {
+    Some(self.total())
+  }
+
+  /// Grows from 0 at the prior towards 1 as evidence piles up.
+  fn confidence(&self) -> f64 {
+    (1.0 - PRIOR_CONCENTRATION / self.total()).max(0.0)
+  }
+}
+
+/// Counts of what someone has seen first-hand in each kind of biome, which
+/// beliefs fall back on as they're forgotten.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BiomePrior {
+  counts: [[f64; Resource::variant_count()]; Biome::variant_count()],
+}
+
+impl Default for BiomePrior {
+  fn default() -> Self {
+    Self {
+      counts: [[PRIOR_COUNT; Resource::variant_count()];
+        Biome::variant_count()],
+    }
+  }
+}
+
+impl BiomePrior {
+  /// Counts seeing `resource` in `biome`, weighted by `certainty`.
+  pub fn learn(&mut self, biome: &Biome, resource: Resource, certainty: f64) {
+    self.counts[biome.ordinal() as usize][resource.ordinal() as usize] +=
+      certainty;
+  }
+
+  /// What's likely in any cell of `biome`, even until something's been seen
+  /// there.
+  pub fn prior(&self, biome: &Biome) -> ResourceProbability {
+    let counts = &self.counts[biome.ordinal() as usize];
+    let total: f64 = counts.iter().sum();
+    ResourceProbability::from_fn(|r| counts[r.ordinal() as usize] / total)
+  }
+}
+
+/// A probability for every resource, held in a more compact form and
+/// unpacked for every update.
+pub trait Packed: Clone {
+  fn pack(distribution: &ResourceProbability) -> Self;
+
+  fn unpack(&self) -> ResourceProbability;
+
+  fn update<F: FnOnce(&mut ResourceProbability)>(&mut self, f: F) {
+    let mut distribution = self.unpack();
+    f(&mut distribution);
+    *self = Self::pack(&distribution);
+  }
+}
+
+impl<P: Packed> Belief for P {
+  fn from_distribution(distribution: &ResourceProbability) -> Self {
+    P::pack(distribution)
+  }
+
+  fn observe(&mut self, sensed: Resource, accuracy: f64) {
+    self.update(|d| d.observe(sensed, accuracy));
+  }
+
+  fn merge(
+    &mut self,
+    other: &ResourceProbability,
+    _concentration: Option<f64>,
+    trust: f64,
+  ) {
+    self.update(|d| d.adjust_towards(other, trust));
+  }
+
+  fn decay(&mut self, percent: f64) {
+    self.update(|d| d.resdistribute(percent));
+  }
+
+  fn decay_towards(&mut self, percent: f64, prior: &ResourceProbability) {
+    self.update(|d| d.fade_towards(prior, percent));
+  }
+
+  fn most_likely(&self) -> Resource {
+    self.unpack().plurality()
+  }
+
+  fn probability(&self, resource: Resource) -> f64 {
+    self.unpack().get(resource)
+  }
+
+  fn distribution(&self) -> ResourceProbability {
+    self.unpack()
+  }
+}
+
+/// Probabilities in single precision.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Probability32([f32; Resource::variant_count()]);
+
+impl Packed for Probability32 {
+  fn pack(distribution: &ResourceProbability) -> Self {
+    let mut inner = [0.0; Resource::variant_count()];
+    for (v, p) in inner.iter_mut().zip(distribution.clone().into_inner()) {
+      *v = p as f32;
+    }
+    Self(inner)
+  }
+
+  fn unpack(&self) -> ResourceProbability {
+    ResourceProbability::from_fn(|r| self.0[r.ordinal() as usize] as f64)
+  }
+}
+
+/// Probabilities rounded to 255ths, a byte each. Changes smaller than that
+/// are lost, so decay slower than 1/255 a tick is refused by the config.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Probability8([u8; Resource::variant_count()]);
+
+impl Packed for Probability8 {
+  fn pack(distribution: &ResourceProbability) -> Self {
+    let mut inner = [0; Resource::variant_count()];
+    for (v, p) in inner.iter_mut().zip(distribution.clone().into_inner()) {
+      *v = (p * 255.0).round() as u8;
+    }
+    Self(inner)
+  }
+
+  fn unpack(&self) -> ResourceProbability {
+    let total: f64 = self.0.iter().map(|v| *v as f64).sum();
+    if total == 0.0 {
+      return ResourceProbability::even();
+    }
+    ResourceProbability::from_fn(|r| {
+      self.0[r.ordinal() as usize] as f64 / total
+    })
+  }
+}
+
+/// Every belief of one person, all held the same way.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Beliefs {
+  Probability(Grid<ResourceProbability>),
+  Probability32(Grid<Probability32>),
+  Probability8(Grid<Probability8>),
+  LastSeen(Grid<LastSeen>),
+  Dirichlet(Grid<Dirichlet>),
+}
+
+/// Evaluates `$body` with `$grid` bound to the grid of beliefs, whatever kind
+/// they are.
+macro_rules! with_grid {
+  ($beliefs:expr, $grid:ident => $body:expr) => {
+    match $beliefs {
+      Beliefs::Probability($grid) => $body,
+      Beliefs::Probability32($grid) => $body,
+      Beliefs::Probability8($grid) => $body,
+      Beliefs::LastSeen($grid) => $body,
+      Beliefs::Dirichlet($grid) => $body,
+    }
+  };
+}
+
+impl Beliefs {
+  /// Beliefs of `model` about a map of `dim` cells, held as `memory` has it.
+  pub fn gen<R: Rng>(
+    rng: &mut R,
+    dim: (usize, usize),
+    model: BeliefModel,
+    memory: &MemoryConfig,
+  ) -> Self {
+    let (layout, initial) = (memory.layout(), memory.initial);
+    match (model, memory.precision) {
+      (BeliefModel::Probability, Precision::F64) => {
+        Beliefs::Probability(grid(rng, dim, layout, initial))
+      }
+      (BeliefModel::Probability, Precision::F32) => {
+        Beliefs::Probability32(grid(rng, dim, layout, initial))
+      }
+      (BeliefModel::Probability, Precision::U8) => {
+        Beliefs::Probability8(grid(rng, dim, layout, initial))
+      }
+      (BeliefModel::LastSeen, _) => {
+        Beliefs::LastSeen(grid(rng, dim, layout, initial))
+      }
+      (BeliefModel::Dirichlet, _) => {
+        Beliefs::Dirichlet(grid(rng, dim, layout, initial))
+      }
+    }
+  }
+
+  pub fn model(&self) -> BeliefModel {
+    match self {
+      Beliefs::Probability(_)
+      | Beliefs::Probability32(_)
+      | Beliefs::Probability8(_) => BeliefModel::Probability,
+      Beliefs::LastSeen(_) => BeliefModel::LastSeen,
+      Beliefs::Dirichlet(_) => BeliefModel::Dirichlet,
+    }
+  }
+
+  pub fn dim(&self) -> (usize, usize) {
+    with_grid!(self, grid => grid.dim())
+  }
+
+  pub fn len(&self) -> usize {
+    with_grid!(self, grid => grid.len())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn get(&self, i: (usize, usize)) -> &dyn Belief {
+    with_grid!(self, grid => &grid[i])
+  }
+
+  pub fn get_mut(&mut self, i: (usize, usize)) -> &mut dyn Belief {
+    with_grid!(self, grid => &mut grid[i])
+  }
+
+  /// Whether the belief about a cell is still the one it started out with
+  /// before anything was learned, see `Grid::default`.
+  pub fn is_default(&self, i: (usize, usize)) -> bool {
+    with_grid!(self, grid => grid.is_default(i))
+  }
+
+  /// Replaces the belief about a cell with `distribution`.
+  pub fn set(&mut self, i: (usize, usize), distribution: &ResourceProbability) {
+    with_grid!(
+      self,
+      grid => grid.set(i, Belief::from_distribution(distribution))
+    )
+  }
+
+  /// Applies `f` to the belief about a cell, see `Grid::update`.
+  pub fn update<F: FnOnce(&mut dyn Belief)>(
+    &mut self,
+    i: (usize, usize),
+    f: F,
+  ) {
+    with_grid!(self, grid => grid.update(i, |b| f(b)))
+  }
+
+  /// Beliefs with storage of their own, see `Grid::stored`.
+  pub fn stored(&self) -> usize {
+    with_grid!(self, grid => grid.stored())
+  }
+
+  /// Forgets everything about a cell, see `Grid::clear`.
+  pub fn clear(&mut self, i: (usize, usize)) {
+    with_grid!(self, grid => grid.clear(i))
+  }
+
+  /// Calls `f` with every belief and the cell it's about, row by row.
+  pub fn for_each<F: FnMut((usize, usize), &dyn Belief)>(&self, mut f: F) {
+    with_grid!(self, grid => grid.for_each(|i, b| f(i, b)))
+  }
+
+  /// Calls `f` with every belief that's stored, see
+  /// `Grid::for_each_stored_mut`.
+  pub fn for_each_stored_mut<F: FnMut((usize, usize), &mut dyn Belief)>(
+    &mut self,
+    mut f: F,
+  ) {
+    with_grid!(self, grid => grid.for_each_stored_mut(|i, b| f(i, b)))
+  }
+
+  /// Calls `f` with every belief, see `Grid::for_each_mut`.
+  pub fn for_each_mut<F: FnMut((usize, usize), &mut dyn Belief)>(
+    &mut self,
+    mut f: F,
+  ) {
+    with_grid!(self, grid => grid.for_each_mut(|i, b| f(i, b)))
+  }
+
+  /// Applies `f` to every belief into a dense array.
+  pub fn map<T, F: FnMut(&dyn Belief) -> T>(&self, mut f: F) -> Array2<T> {
+    with_grid!(self, grid => grid.to_array(|b| f(b)))
+  }
+}
+
+/// Beliefs laid out as `layout`, starting out as `initial` has it. Guesses
+/// are drawn cell by cell, row by row, whatever the layout, so that it
+/// doesn't change what anyone believes.
+fn grid<B: Belief + Clone, R: Rng>(
+  rng: &mut R,
+  dim: (usize, usize),
+  layout: Layout,
+  initial: InitialBeliefs,
+) -> Grid<B> {
+  let even = B::from_distribution(&ResourceProbability::even());
+  let mut guess = || B::from_distribution(&ResourceProbability::gen(rng));
+  match (initial, layout) {
+    (InitialBeliefs::Even, _) => Grid::from_elem(dim, even, layout),
+    (InitialBeliefs::Guess, Layout::Dense) => {
+      Grid::from_array(Array2::from_shape_simple_fn(dim, guess), even)
+    }
+    (InitialBeliefs::Guess, _) => {
+      let mut grid = Grid::from_elem(dim, even, layout);
+      for x in 0..dim.0 {
+        for y in 0..dim.1 {
+          grid[(x, y)] = guess();
+        }
+      }
+      grid
+    }
+  }
+}
@@ -0,0 +1,104 @@
+use std::{fs, path::PathBuf};
+
+use clap::Args;
+use serde::Serialize;
+
+use info_distribution::{export, metrics::TickMetrics, snapshot, State};
+
+use crate::record::RecordArgs;
+
+#[derive(Args)]
+pub(crate) struct HeadlessArgs {
+  /// Number of ticks to simulate, stopping early if everyone dies
+  #[arg(long, default_value_t = 1000)]
+  ticks: u64,
+
+  /// File to write the summary to, stdout if omitted
+  #[arg(long)]
+  output: Option<PathBuf>,
+
+  /// Snapshot file to save the final state to, JSON if it ends in .json
+  #[arg(long)]
+  save: Option<PathBuf>,
+
+  /// File to stream the metrics of every tick to, as .csv, .jsonl or .json
+  #[arg(long)]
+  metrics: Option<PathBuf>,
+
+  #[command(flatten)]
+  record: RecordArgs,
+}
+
+#[derive(Serialize)]
+struct Summary {
+  seed: u64,
+  ticks: u64,
+  initial_population: usize,
+  population: usize,
+  mean_individual_error: Option<f64>,
+  mean_honest_error: Option<f64>,
+  collective_error: Option<f64>,
+  mean_entropy: Option<f64>,
+}
+
+impl Summary {
+  fn new(state: &State, initial_population: usize, last: &TickMetrics) -> Self {
+    Self {
+      seed: state.seed(),
+      ticks: state.tick(),
+      initial_population,
+      population: last.population,
+      mean_individual_error: last.mean_individual_error,
+      mean_honest_error: last.mean_honest_error,
+      collective_error: last.collective_error,
+      mean_entropy: last.mean_entropy,
+    }
+  }
+}
+
+pub(crate) fn run(
+  mut state: State,
+  args: &HeadlessArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+  let initial_population = state.people().len();
+  let mut exporter = match &args.metrics {
+    Some(path) => Some(export::create(path, &state)?),
+    None => None,
+  };
+  let mut recorder = args.record.recorder()?;
+  if let Some(recorder) = &mut recorder {
+    recorder.record(&state)?;
+  }
+
+  let end = state.tick() + args.ticks;
+  while state.tick() < end && !state.people().is_empty() {
+    state.update();
+    if let Some(exporter) = &mut exporter {
+      exporter.export(&TickMetrics::collect(&state))?;
+    }
+    if let Some(recorder) = &mut recorder {
+      recorder.record(&state)?;
+    }
+  }
+
+  if let Some(exporter) = &mut exporter {
+    exporter.finish()?;
+  }
+  if let Some(recorder) = &mut recorder {
+    recorder.finish()?;
+  }
+
+  if let Some(path) = &args.save {
+    snapshot::save(&state, path)?;
+  }
+
+  let last = TickMetrics::collect(&state);
+  let summary =
+    toml::to_string(&Summary::new(&state, initial_population, &last))?;
+  match &args.output {
+    Some(path) => fs::write(path, summary)?,
+    None => print!("{}", summary),
+  }
+
+  Ok(())
+}
@@ -0,0 +1,102 @@
+mod headless;
+mod record;
+mod sweep;
+#[cfg(feature = "viewer")]
+mod view;
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use headless::HeadlessArgs;
+use info_distribution::{snapshot, SimConfig, State};
+use rand::prelude::*;
+use sweep::SweepArgs;
+#[cfg(feature = "viewer")]
+use view::ViewArgs;
+
+#[derive(Parser)]
+#[command(about = "Simulates how information spreads between people")]
+struct Cli {
+  /// TOML file with simulation parameters, defaults are used if omitted
+  #[arg(long, global = true)]
+  config: Option<PathBuf>,
+
+  /// Seed for the run, overriding the one in the config file
+  #[arg(long, global = true)]
+  seed: Option<u64>,
+
+  /// Snapshot to resume from instead of generating a new world
+  #[arg(long, global = true, conflicts_with_all = ["config", "seed"])]
+  load: Option<PathBuf>,
+
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Open a window showing the simulation (the default)
+  #[cfg(feature = "viewer")]
+  View(ViewArgs),
+  /// Run the simulation without a window and print a summary
+  Headless(HeadlessArgs),
+  /// Run many headless simulations over ranges of parameters and seeds
+  Sweep(SweepArgs),
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+  let mut cli = Cli::parse();
+
+  let config = match &cli.config {
+    Some(path) => SimConfig::load(path)?,
+    None => SimConfig::default(),
+  };
+
+  let command = match cli.command.take() {
+    Some(command) => command,
+    None => default_command()?,
+  };
+  match command {
+    #[cfg(feature = "viewer")]
+    Command::View(args) => {
+      let state = initial_state(&cli, config)?;
+      show_image::run_context(move || view::run(state, args))
+    }
+    Command::Headless(args) => {
+      headless::run(initial_state(&cli, config)?, &args)
+    }
+    Command::Sweep(args) => sweep::run(config, &args),
+  }
+}
+
+/// What runs without a subcommand: the viewer, if it's built in.
+#[cfg(feature = "viewer")]
+fn default_command() -> Result<Command, Box<dyn std::error::Error>> {
+  Ok(Command::View(ViewArgs::default()))
+}
+
+#[cfg(not(feature = "viewer"))]
+fn default_command() -> Result<Command, Box<dyn std::error::Error>> {
+  Err(
+    "built without the viewer, pick a subcommand such as headless or rebuild \
+     with --features viewer"
+      .into(),
+  )
+}
+
+fn initial_state(
+  cli: &Cli,
+  config: SimConfig,
+) -> Result<State, Box<dyn std::error::Error>> {
+  match &cli.load {
+    Some(path) => Ok(snapshot::load(path)?),
+    None => {
+      let seed = cli
+        .seed
+        .or(config.seed)
+        .unwrap_or_else(|| thread_rng().gen());
+
+      Ok(State::gen(config, seed)?)
+    }
+  }
+}
@@ -0,0 +1,52 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use info_distribution::{
+  graphics::Panel,
+  record::{RecordError, RecordOptions, Recorder},
+};
+
+#[derive(Args, Default)]
+pub(crate) struct RecordArgs {
+  /// Record frames to a .gif, an animated .png, or a directory of PNGs
+  #[arg(long)]
+  record: Option<PathBuf>,
+
+  /// Record only every Nth tick
+  #[arg(long, default_value_t = 1)]
+  record_every: u64,
+
+  /// Comma separated panels to record, the viewer's if omitted: map, people,
+  /// brain, provenance, certainty, favorability, error, collective-error
+  #[arg(long, value_delimiter = ',')]
+  record_panels: Vec<Panel>,
+
+  /// Time each frame is shown for in animations, in milliseconds
+  #[arg(long, default_value_t = 100)]
+  record_delay: u16,
+}
+
+impl RecordArgs {
+  pub(crate) fn recorder(&self) -> Result<Option<Recorder>, RecordError> {
+    let path = match &self.record {
+      Some(path) => path,
+      None => return Ok(None),
+    };
+
+    let panels = if self.record_panels.is_empty() {
+      Panel::ALL.to_vec()
+    } else {
+      self.record_panels.clone()
+    };
+
+    Recorder::create(
+      path,
+      RecordOptions {
+        every: self.record_every,
+        panels,
+        delay: self.record_delay,
+      },
+    )
+    .map(Some)
+  }
+}
@@ -0,0 +1,41 @@
+use std::{fs::File, path::PathBuf};
+
+use clap::Args;
+use info_distribution::{
+  sweep::{self, SweepSpec},
+  SimConfig,
+};
+
+#[derive(Args)]
+pub(crate) struct SweepArgs {
+  /// TOML file describing the parameters to sweep
+  spec: PathBuf,
+
+  /// CSV file to write the aggregated table to
+  #[arg(long, default_value = "sweep.csv")]
+  output: PathBuf,
+
+  /// Number of runs to execute at once, all cores if omitted
+  #[arg(long)]
+  threads: Option<usize>,
+}
+
+pub(crate) fn run(
+  base: SimConfig,
+  args: &SweepArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+  let spec = SweepSpec::load(&args.spec)?;
+
+  let pool = rayon::ThreadPoolBuilder::new()
+    .num_threads(args.threads.unwrap_or(0))
+    .build()?;
+
+  let results = pool.install(|| {
+    sweep::run(&base, &spec, |done, total| {
+      eprintln!("Finished run {}/{}", done, total)
+    })
+  })?;
+
+  results.write_csv(File::create(&args.output)?, &base, &spec)?;
+  Ok(())
+}
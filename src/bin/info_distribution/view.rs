@@ -0,0 +1,133 @@
+use std::{path::PathBuf, sync::mpsc::TryRecvError};
+
+use clap::Args;
+use info_distribution::{
+  export,
+  graphics::Panel,
+  metrics::TickMetrics,
+  snapshot::{self, SnapshotFormat},
+  State,
+};
+use show_image::{
+  create_window,
+  event::{VirtualKeyCode, WindowEvent},
+  WindowOptions,
+};
+
+use crate::record::RecordArgs;
+
+#[derive(Args, Default)]
+pub(crate) struct ViewArgs {
+  /// Directory snapshots are saved to when pressing W
+  #[arg(long, default_value = ".")]
+  snapshot_dir: PathBuf,
+
+  /// Save snapshots as JSON instead of the binary format
+  #[arg(long)]
+  snapshot_json: bool,
+
+  /// File to stream the metrics of every tick to, as .csv, .jsonl or .json
+  #[arg(long)]
+  metrics: Option<PathBuf>,
+
+  #[command(flatten)]
+  record: RecordArgs,
+}
+
+pub(crate) fn run(
+  mut state: State,
+  args: ViewArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+  eprintln!("Seed: {}", state.seed());
+
+  let snapshot_format = if args.snapshot_json {
+    SnapshotFormat::Json
+  } else {
+    SnapshotFormat::Binary
+  };
+  let mut exporter = match &args.metrics {
+    Some(path) => Some(export::create(path, &state)?),
+    None => None,
+  };
+  let mut recorder = args.record.recorder()?;
+  if let Some(recorder) = &mut recorder {
+    recorder.record(&state)?;
+  }
+  let mut step = |state: &mut State| -> Result<(), Box<dyn std::error::Error>> {
+    state.update();
+    if let Some(exporter) = &mut exporter {
+      exporter.export(&TickMetrics::collect(state))?;
+    }
+    if let Some(recorder) = &mut recorder {
+      recorder.record(state)?;
+    }
+    Ok(())
+  };
+  let mut running = false;
+  let mut panels = Panel::ALL;
+
+  let window = create_window(
+    "Info Distribution",
+    WindowOptions {
+      size: Some([1920, 1080]),
+      ..Default::default()
+    },
+  )?;
+  window.set_image("image", state.draw_panels(&panels))?;
+
+  let window_events = window.event_channel()?;
+  loop {
+    match window_events.try_recv() {
+      Ok(WindowEvent::KeyboardInput(event)) => {
+        if !event.input.state.is_pressed() {
+          continue;
+        }
+        match event.input.key_code {
+          Some(VirtualKeyCode::Escape) => break,
+          Some(VirtualKeyCode::Left) => state.select_previous_person(),
+          Some(VirtualKeyCode::Right) => state.select_next_person(),
+          Some(VirtualKeyCode::Space) if !running => step(&mut state)?,
+          Some(VirtualKeyCode::S) => running = !running,
+          Some(VirtualKeyCode::P) => {
+            for panel in panels.iter_mut() {
+              *panel = match *panel {
+                Panel::Brain => Panel::Provenance,
+                Panel::Provenance => Panel::Certainty,
+                Panel::Certainty => Panel::Brain,
+                panel => panel,
+              };
+            }
+          }
+          Some(VirtualKeyCode::W) => {
+            let path = args.snapshot_dir.join(format!(
+              "snapshot-{}-{}.{}",
+              state.seed(),
+              state.tick(),
+              snapshot_format.extension()
+            ));
+            snapshot::save(&state, &path)?;
+            eprintln!("Saved snapshot to {}", path.display());
+            continue;
+          }
+          _ => continue,
+        }
+        window.set_image("image", state.draw_panels(&panels))?;
+      }
+      Err(TryRecvError::Empty) if running => {
+        step(&mut state)?;
+        window.set_image("image", state.draw_panels(&panels))?;
+      }
+      Err(TryRecvError::Disconnected) => break,
+      _ => continue,
+    }
+  }
+
+  if let Some(exporter) = &mut exporter {
+    exporter.finish()?;
+  }
+  if let Some(recorder) = &mut recorder {
+    recorder.finish()?;
+  }
+
+  Ok(())
+}
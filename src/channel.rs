@@ -0,0 +1,89 @@
+use std::collections::BTreeMap;
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ChannelConfig;
+use crate::message::{Message, Traffic};
+
+/// Carries messages between people, adding noise and holding them back
+/// until they've crossed their link's latency.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Channel {
+  /// Latency of every link a message has been sent along, by the lowest and
+  /// then the highest id at either end.
+  latencies: BTreeMap<usize, BTreeMap<usize, u64>>,
+  in_flight: Vec<InFlight>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct InFlight {
+  arrives: u64,
+  to: usize,
+  message: Message,
+}
+
+impl Channel {
+  /// Sends a message to the person with id `to`, distorting it on the way.
+  pub fn send<R: Rng>(
+    &mut self,
+    rng: &mut R,
+    config: &ChannelConfig,
+    tick: u64,
+    to: usize,
+    mut message: Message,
+    traffic: &mut Traffic,
+  ) {
+    if config.drop > 0.0 {
+      let before = message.cells.len();
+      message.cells.retain(|_| !rng.gen_bool(config.drop));
+      traffic.dropped += (before - message.cells.len()) as u64;
+    }
+    for shared in message.cells.iter_mut() {
+      if config.noise > 0.0 {
+        shared.belief.perturb(rng, config.noise);
+      }
+      if config.substitution > 0.0 && rng.gen_bool(config.substitution) {
+        shared.belief.substitute(rng);
+      }
+    }
+
+    let latency = *self
+      .latencies
+      .entry(message.from.min(to))
+      .or_default()
+      .entry(message.from.max(to))
+      .or_insert_with(|| {
+        if config.max_latency > config.min_latency {
+          rng.gen_range(config.min_latency..=config.max_latency)
+        } else {
+          config.min_latency
+        }
+      });
+
+    self.in_flight.push(InFlight {
+      arrives: tick + latency,
+      to,
+      message,
+    });
+  }
+
+  /// Takes the messages that have arrived by `tick`, in the order they were
+  /// sent, along with the id of who they're for.
+  pub fn receive(&mut self, tick: u64) -> Vec<(usize, Message)> {
+    let (arrived, in_flight) = std::mem::take(&mut self.in_flight)
+      .into_iter()
+      .partition(|m| m.arrives <= tick);
+    self.in_flight = in_flight;
+    arrived.into_iter().map(|m| (m.to, m.message)).collect()
+  }
+
+  /// Number of messages sent but not yet received.
+  pub fn in_flight(&self) -> usize {
+    self.in_flight.len()
+  }
+
+  pub fn latency(&self, a: usize, b: usize) -> Option<u64> {
+    self.latencies.get(&a.min(b))?.get(&a.max(b)).copied()
+  }
+}
@@ -1,11 +1,800 @@
-pub const NUM_BIOMES: usize = 100;
-pub const MAP_WIDTH: usize = 200;
-pub const MAP_HEIGHT: usize = 200;
+use std::{
+  fmt, fs, io,
+  path::{Path, PathBuf},
+};
 
-pub const NUM_PEROPLE: usize = 10;
+use serde::{Deserialize, Serialize};
 
-pub const MAX_HUNGER: u16 = 1000;
-pub const MAX_THIRST: u16 = 500;
+use crate::grid::Layout;
+use crate::systems::BUILTIN_SYSTEMS;
 
-pub const HUNGER_PER_FOOD: u16 = 10;
-pub const THIRST_PER_WATER: u16 = 10;
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimConfig {
+  /// Seed for every random choice in a run, picked at random if omitted.
+  pub seed: Option<u64>,
+  pub map: MapConfig,
+  pub people: PeopleConfig,
+  pub needs: NeedsConfig,
+  pub perception: PerceptionConfig,
+  pub memory: MemoryConfig,
+  pub communication: CommunicationConfig,
+  pub network: NetworkConfig,
+  pub channel: ChannelConfig,
+  pub pipeline: PipelineConfig,
+}
+
+impl SimConfig {
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+    Self::from_toml_str(&fs::read_to_string(path)?)
+  }
+
+  pub fn from_toml_str(s: &str) -> Result<Self, ConfigError> {
+    let config: Self = toml::from_str(s)?;
+    config.validate()?;
+    Ok(config)
+  }
+
+  /// Returns a copy of the config with the parameter at a dotted path, such
+  /// as `perception.sense_range`, set to `value`.
+  pub fn with_parameter(
+    &self,
+    path: &str,
+    value: toml::Value,
+  ) -> Result<Self, ConfigError> {
+    let mut root = toml::Value::try_from(self)
+      .map_err(|e| ConfigError::Invalid(e.to_string()))?;
+
+    let unknown =
+      || ConfigError::Invalid(format!("unknown parameter {}", path));
+    let (sections, key) = match path.rsplit_once('.') {
+      Some((sections, key)) => (Some(sections), key),
+      None => (None, path),
+    };
+
+    let mut table = root.as_table_mut().ok_or_else(unknown)?;
+    for section in sections.into_iter().flat_map(|s| s.split('.')) {
+      table = table
+        .get_mut(section)
+        .and_then(|v| v.as_table_mut())
+        .ok_or_else(unknown)?;
+    }
+    // Unknown keys are caught by `deny_unknown_fields` below, inserting
+    // rather than replacing lets unset optional parameters be swept too.
+    table.insert(key.to_owned(), value);
+
+    let config: Self = root.try_into()?;
+    config.validate()?;
+    Ok(config)
+  }
+
+  pub fn validate(&self) -> Result<(), ConfigError> {
+    self.map.validate()?;
+    self.people.validate()?;
+    self.needs.validate()?;
+    self.perception.validate()?;
+    self.memory.validate()?;
+    self.communication.validate()?;
+    self.network.validate(self.people.count)?;
+    self.channel.validate()?;
+    self.pipeline.validate()?;
+    Ok(())
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MapConfig {
+  pub width: usize,
+  pub height: usize,
+  pub num_biomes: usize,
+  pub mutation: MutationConfig,
+}
+
+impl Default for MapConfig {
+  fn default() -> Self {
+    Self {
+      width: 200,
+      height: 200,
+      num_biomes: 100,
+      mutation: MutationConfig::default(),
+    }
+  }
+}
+
+impl MapConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure(self.width > 0, "map.width must be greater than 0")?;
+    ensure(self.height > 0, "map.height must be greater than 0")?;
+    ensure(self.num_biomes > 0, "map.num_biomes must be greater than 0")?;
+    self.mutation.validate()
+  }
+}
+
+/// Per cell, per tick probabilities of the map changing under the people.
+/// Whatever probability is left over keeps the cell as it is.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MutationConfig {
+  /// Copy the resource of a random neighbouring cell.
+  pub neighbor: f64,
+  /// Generate a fresh resource from the cell's biome.
+  pub biome: f64,
+  /// Replace the cell with any resource at all.
+  pub random: f64,
+}
+
+impl Default for MutationConfig {
+  fn default() -> Self {
+    Self {
+      neighbor: 0.00009,
+      biome: 0.000009,
+      random: 0.000001,
+    }
+  }
+}
+
+impl MutationConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure_probability(self.neighbor, "map.mutation.neighbor")?;
+    ensure_probability(self.biome, "map.mutation.biome")?;
+    ensure_probability(self.random, "map.mutation.random")?;
+    ensure(
+      self.neighbor + self.biome + self.random <= 1.0,
+      "map.mutation probabilities must not sum to more than 1",
+    )
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PeopleConfig {
+  pub count: usize,
+  /// Fraction of people who lie about what they believe.
+  pub liars: f64,
+  /// How liars distort what they share.
+  pub lie: Lie,
+  /// How many people hold their beliefs each way.
+  pub beliefs: BeliefMix,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Lie {
+  /// Report nothing where they believe there's food.
+  HideFood,
+  /// Report food where they believe there's water and the other way around.
+  SwapFoodWater,
+  /// Report ghosts where they believe there's nothing.
+  InventGhosts,
+}
+
+impl Default for PeopleConfig {
+  fn default() -> Self {
+    Self {
+      count: 10,
+      liars: 0.0,
+      lie: Lie::HideFood,
+      beliefs: BeliefMix::default(),
+    }
+  }
+}
+
+impl PeopleConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure(self.count > 0, "people.count must be greater than 0")?;
+    ensure_probability(self.liars, "people.liars")?;
+    self.beliefs.validate()
+  }
+
+  /// Number of people who lie, rounded to the nearest person.
+  pub fn liar_count(&self) -> usize {
+    (self.count as f64 * self.liars).round() as usize
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BeliefModel {
+  /// A probability for every resource.
+  Probability,
+  /// The last resource seen or heard of, with a confidence that fades.
+  LastSeen,
+  /// Pseudo-counts of how often each resource has been seen.
+  Dirichlet,
+}
+
+/// Relative share of people holding their beliefs each way.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BeliefMix {
+  pub probability: f64,
+  pub last_seen: f64,
+  pub dirichlet: f64,
+}
+
+impl Default for BeliefMix {
+  fn default() -> Self {
+    Self {
+      probability: 1.0,
+      last_seen: 0.0,
+      dirichlet: 0.0,
+    }
+  }
+}
+
+impl BeliefMix {
+  fn validate(&self) -> Result<(), ConfigError> {
+    let shares = self.shares();
+    ensure(
+      shares.iter().all(|(_, share)| *share >= 0.0),
+      "people.beliefs shares must not be negative",
+    )?;
+    ensure(
+      shares.iter().any(|(_, share)| *share > 0.0),
+      "people.beliefs shares must not all be 0",
+    )
+  }
+
+  fn shares(&self) -> [(BeliefModel, f64); 3] {
+    [
+      (BeliefModel::Probability, self.probability),
+      (BeliefModel::LastSeen, self.last_seen),
+      (BeliefModel::Dirichlet, self.dirichlet),
+    ]
+  }
+
+  /// Belief model of each of `count` people, grouped by model, with the
+  /// number of people holding each rounded so they add up to `count`.
+  pub fn models(&self, count: usize) -> Vec<BeliefModel> {
+    let shares = self.shares();
+    let total: f64 = shares.iter().map(|(_, share)| share).sum();
+    let exact: Vec<_> = shares
+      .iter()
+      .map(|(_, share)| count as f64 * share / total)
+      .collect();
+    let mut counts: Vec<_> = exact.iter().map(|e| e.floor() as usize).collect();
+
+    // Hand out the people lost to rounding down to the largest remainders.
+    let mut by_remainder: Vec<_> = (0..shares.len()).collect();
+    by_remainder.sort_by(|a, b| {
+      let remainder = |i: usize| exact[i] - exact[i].floor();
+      remainder(*b).partial_cmp(&remainder(*a)).unwrap()
+    });
+    let missing = count - counts.iter().sum::<usize>();
+    for i in by_remainder.into_iter().take(missing) {
+      counts[i] += 1;
+    }
+
+    shares
+      .iter()
+      .zip(counts)
+      .flat_map(|((model, _), count)| std::iter::repeat_n(*model, count))
+      .collect()
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NeedsConfig {
+  pub max_hunger: u16,
+  pub max_thirst: u16,
+  pub hunger_per_food: u16,
+  pub thirst_per_water: u16,
+}
+
+impl Default for NeedsConfig {
+  fn default() -> Self {
+    Self {
+      max_hunger: 1000,
+      max_thirst: 500,
+      hunger_per_food: 10,
+      thirst_per_water: 10,
+    }
+  }
+}
+
+impl NeedsConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure(
+      self.max_hunger > 0,
+      "needs.max_hunger must be greater than 0",
+    )?;
+    ensure(
+      self.max_thirst > 0,
+      "needs.max_thirst must be greater than 0",
+    )?;
+    ensure(
+      self.hunger_per_food > 0,
+      "needs.hunger_per_food must be greater than 0",
+    )?;
+    ensure(
+      self.thirst_per_water > 0,
+      "needs.thirst_per_water must be greater than 0",
+    )
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PerceptionConfig {
+  pub sense_range: usize,
+  /// How what's sensed is folded into beliefs.
+  pub update: PerceptionUpdate,
+  /// Probability of sensing the true resource of the cell someone is
+  /// standing on with the `Bayesian` update, falling to chance at the edge
+  /// of their sense range.
+  pub accuracy: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerceptionUpdate {
+  /// Blends beliefs towards the true resource, harder the closer it is.
+  Heuristic,
+  /// Senses a resource through a noisy sensor and updates beliefs by Bayes'
+  /// rule.
+  Bayesian,
+}
+
+impl Default for PerceptionConfig {
+  fn default() -> Self {
+    Self {
+      sense_range: 10,
+      update: PerceptionUpdate::Heuristic,
+      accuracy: 0.95,
+    }
+  }
+}
+
+impl PerceptionConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure(
+      self.sense_range > 0,
+      "perception.sense_range must be greater than 0",
+    )?;
+    ensure(
+      self.accuracy > 0.0 && self.accuracy < 1.0,
+      "perception.accuracy must be between 0 and 1, exclusive",
+    )
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+  /// Fraction of every belief forgotten each tick, towards whatever `model`
+  /// decays to.
+  pub decay: f64,
+  /// Age in ticks at which a belief decays at the full rate, fresher ones
+  /// decaying proportionally slower and older ones faster. Every belief
+  /// decays at the same rate if omitted.
+  pub age_scale: Option<f64>,
+  /// What people believe about each cell before learning anything.
+  pub initial: InitialBeliefs,
+  /// How each person's beliefs about the map are laid out in memory.
+  pub storage: Storage,
+  /// Cells a side of each tile with `Tiled` storage.
+  pub tile_size: usize,
+  /// How precisely probability beliefs are stored.
+  pub precision: Precision,
+  /// What beliefs decay towards and how fast.
+  pub model: DecayModel,
+  /// Distance from home at which `Distance` decay is twice as fast.
+  pub distance_scale: f64,
+  /// Cells remembered with `Capacity` decay.
+  pub capacity: usize,
+}
+
+#[derive(
+  Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum DecayModel {
+  /// Every belief fades towards even.
+  #[default]
+  Uniform,
+  /// Beliefs fade towards what the person has seen of the cell's biome.
+  BiomePrior,
+  /// Beliefs fade faster the further the cell is from the person's home.
+  Distance,
+  /// Only the most recently observed cells are remembered, fading towards
+  /// even, and the rest are forgotten outright.
+  Capacity,
+}
+
+#[derive(
+  Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum InitialBeliefs {
+  /// A random distribution for every cell.
+  #[default]
+  Guess,
+  /// Every resource equally likely everywhere.
+  Even,
+}
+
+/// How beliefs are laid out, which never changes what happens, only how much
+/// memory it takes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Storage {
+  /// Every cell stored.
+  Dense,
+  /// Only cells that aren't even are stored.
+  Sparse,
+  /// Cells stored in tiles, each sharing one even tile until a cell in it
+  /// isn't even.
+  Tiled,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Precision {
+  F64,
+  F32,
+  /// A byte per resource, which needs a `decay` of at least 1/255 a tick and
+  /// no `age_scale`, since anything slower is rounded away.
+  U8,
+}
+
+impl Default for MemoryConfig {
+  fn default() -> Self {
+    Self {
+      decay: 0.0001,
+      age_scale: None,
+      initial: InitialBeliefs::Guess,
+      storage: Storage::Dense,
+      tile_size: 16,
+      precision: Precision::F64,
+      model: DecayModel::Uniform,
+      distance_scale: 50.0,
+      capacity: 1000,
+    }
+  }
+}
+
+impl MemoryConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure_probability(self.decay, "memory.decay")?;
+    if let Some(age_scale) = self.age_scale {
+      ensure(age_scale > 0.0, "memory.age_scale must be greater than 0")?;
+    }
+    ensure(
+      self.tile_size > 0,
+      "memory.tile_size must be greater than 0",
+    )?;
+    ensure(
+      self.distance_scale > 0.0,
+      "memory.distance_scale must be greater than 0",
+    )?;
+    ensure(self.capacity > 0, "memory.capacity must be greater than 0")?;
+    ensure(
+      self.precision != Precision::U8
+        || self.decay == 0.0
+        || self.decay * 255.0 >= 1.0,
+      "memory.decay must be 0 or at least 1/255 with u8 precision, slower \
+       decay is rounded away",
+    )?;
+    ensure(
+      self.precision != Precision::U8
+        || self.decay == 0.0
+        || self.age_scale.is_none(),
+      "memory.age_scale can't be used with u8 precision, fresh beliefs decay \
+       too slowly and are rounded away",
+    )?;
+    Ok(())
+  }
+
+  pub fn layout(&self) -> Layout {
+    match self.storage {
+      Storage::Dense => Layout::Dense,
+      Storage::Sparse => Layout::Sparse,
+      Storage::Tiled => Layout::Tiled {
+        size: self.tile_size,
+      },
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CommunicationConfig {
+  /// Trust in people who haven't been checked on yet.
+  pub trust: f64,
+  /// How far trust moves towards 1 or 0 every time something someone said
+  /// is confirmed or contradicted by perception. Trust is fixed if 0.
+  pub trust_learning_rate: f64,
+  /// Furthest two people can be apart and still talk, anywhere on the map if
+  /// omitted.
+  pub range: Option<f64>,
+  /// Most cells each person shares in a conversation, their whole brain if
+  /// omitted.
+  pub bandwidth: Option<usize>,
+  /// Which cells are shared when the bandwidth is limited.
+  pub selection: ShareSelection,
+  /// Difference in age, in ticks, at which the older of what's heard and
+  /// what's already believed is weighted half as much as the newer one. Age
+  /// is ignored if omitted.
+  pub freshness_half_life: Option<f64>,
+  /// Who tells whom in a conversation.
+  pub protocol: Protocol,
+  /// How many people everyone contacts each tick. If omitted, people are
+  /// paired up instead so everyone has at most one conversation.
+  pub fanout: Option<usize>,
+  /// When people are willing to talk rather than forage.
+  pub policy: TalkPolicy,
+  /// Fraction of their maximum hunger and thirst people with the
+  /// `WhenSated` policy must both be below to talk.
+  pub sated: f64,
+  /// What each conversation costs the people in it.
+  pub cost: TalkCost,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TalkPolicy {
+  /// Talk whenever there's someone to talk to.
+  Always,
+  /// Only talk while not too hungry or thirsty.
+  WhenSated,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TalkCost {
+  /// Hunger added to each person in a conversation.
+  pub hunger: u16,
+  /// Thirst added to each person in a conversation.
+  pub thirst: u16,
+  /// Whether talking takes up the person's next move.
+  pub stay: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+  /// Whoever starts the conversation tells the other.
+  Push,
+  /// Whoever starts the conversation asks the other.
+  Pull,
+  /// Both tell each other.
+  PushPull,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareSelection {
+  /// The cells the speaker is most certain about.
+  Confident,
+  /// The cells closest to the speaker.
+  Nearby,
+  /// The cells most likely to hold food or water.
+  Resources,
+  Random,
+}
+
+impl Default for CommunicationConfig {
+  fn default() -> Self {
+    Self {
+      trust: 0.5,
+      trust_learning_rate: 0.0,
+      range: None,
+      bandwidth: None,
+      selection: ShareSelection::Confident,
+      freshness_half_life: None,
+      protocol: Protocol::PushPull,
+      fanout: None,
+      policy: TalkPolicy::Always,
+      sated: 0.5,
+      cost: TalkCost::default(),
+    }
+  }
+}
+
+impl CommunicationConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure_probability(self.trust, "communication.trust")?;
+    ensure_probability(
+      self.trust_learning_rate,
+      "communication.trust_learning_rate",
+    )?;
+    if let Some(range) = self.range {
+      ensure(range >= 0.0, "communication.range must not be negative")?;
+    }
+    if let Some(half_life) = self.freshness_half_life {
+      ensure(
+        half_life > 0.0,
+        "communication.freshness_half_life must be greater than 0",
+      )?;
+    }
+    ensure_probability(self.sated, "communication.sated")?;
+    Ok(())
+  }
+}
+
+/// Who can talk to whom. Conversations are only held along links.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+  pub kind: NetworkKind,
+  /// Links per person on a ring.
+  pub degree: usize,
+  /// Probability of each ring link being moved to a random person.
+  pub rewire: f64,
+  /// Links each person makes when joining a Barabási–Albert network.
+  pub links: usize,
+  /// File with a pair of person ids per line.
+  pub path: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkKind {
+  /// Everyone is linked to everyone else.
+  Complete,
+  /// Everyone is linked to the `degree` people closest to them on a ring.
+  Ring,
+  /// A ring whose links are each moved to a random person with probability
+  /// `rewire`.
+  WattsStrogatz,
+  /// Grown one person at a time, each linking to `links` others picked in
+  /// proportion to how many links they already have.
+  BarabasiAlbert,
+  /// Links read from the file at `path`.
+  File,
+}
+
+impl Default for NetworkConfig {
+  fn default() -> Self {
+    Self {
+      kind: NetworkKind::Complete,
+      degree: 4,
+      rewire: 0.1,
+      links: 2,
+      path: None,
+    }
+  }
+}
+
+impl NetworkConfig {
+  fn validate(&self, count: usize) -> Result<(), ConfigError> {
+    match self.kind {
+      NetworkKind::Complete => Ok(()),
+      NetworkKind::Ring | NetworkKind::WattsStrogatz => {
+        ensure(
+          self.degree > 0 && self.degree.is_multiple_of(2),
+          "network.degree must be even and greater than 0",
+        )?;
+        ensure(
+          self.degree < count.max(1),
+          "network.degree must be less than people.count",
+        )?;
+        ensure_probability(self.rewire, "network.rewire")
+      }
+      NetworkKind::BarabasiAlbert => {
+        ensure(self.links > 0, "network.links must be greater than 0")?;
+        ensure(
+          self.links < count.max(1),
+          "network.links must be less than people.count",
+        )
+      }
+      NetworkKind::File => ensure(
+        self.path.is_some(),
+        "network.path must be set for a file network",
+      ),
+    }
+  }
+}
+
+/// Noise and delays messages go through on their way between people.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelConfig {
+  /// Standard deviation of the gaussian noise added to every shared
+  /// probability.
+  pub noise: f64,
+  /// Probability of a shared cell's most likely resource being swapped with
+  /// another one.
+  pub substitution: f64,
+  /// Probability of a shared cell being lost.
+  pub drop: f64,
+  /// Ticks messages take to arrive. Every link gets its own latency, picked
+  /// at random between the two the first time it's used.
+  pub min_latency: u64,
+  pub max_latency: u64,
+}
+
+impl ChannelConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    ensure(self.noise >= 0.0, "channel.noise must not be negative")?;
+    ensure_probability(self.substitution, "channel.substitution")?;
+    ensure_probability(self.drop, "channel.drop")?;
+    ensure(
+      self.min_latency <= self.max_latency,
+      "channel.min_latency must not be greater than channel.max_latency",
+    )
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineConfig {
+  /// Systems run every tick, in order.
+  pub systems: Vec<String>,
+}
+
+impl Default for PipelineConfig {
+  fn default() -> Self {
+    Self {
+      systems: BUILTIN_SYSTEMS.iter().map(|s| (*s).to_owned()).collect(),
+    }
+  }
+}
+
+impl PipelineConfig {
+  fn validate(&self) -> Result<(), ConfigError> {
+    for system in self.systems.iter() {
+      ensure(
+        BUILTIN_SYSTEMS.contains(&system.as_str()),
+        &format!("pipeline.systems has unknown system {}", system),
+      )?;
+    }
+    Ok(())
+  }
+}
+
+fn ensure(condition: bool, message: &str) -> Result<(), ConfigError> {
+  if condition {
+    Ok(())
+  } else {
+    Err(ConfigError::Invalid(message.to_owned()))
+  }
+}
+
+fn ensure_probability(value: f64, name: &str) -> Result<(), ConfigError> {
+  ensure(
+    (0.0..=1.0).contains(&value),
+    &format!("{} must be between 0 and 1", name),
+  )
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+  Io(io::Error),
+  Parse(toml::de::Error),
+  Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ConfigError::Io(e) => write!(f, "failed to read config: {}", e),
+      ConfigError::Parse(e) => write!(f, "failed to parse config: {}", e),
+      ConfigError::Invalid(e) => write!(f, "invalid config: {}", e),
+    }
+  }
+}
+
+impl std::error::Error for ConfigError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      ConfigError::Io(e) => Some(e),
+      ConfigError::Parse(e) => Some(e),
+      ConfigError::Invalid(_) => None,
+    }
+  }
+}
+
+impl From<io::Error> for ConfigError {
+  fn from(e: io::Error) -> Self {
+    ConfigError::Io(e)
+  }
+}
+
+impl From<toml::de::Error> for ConfigError {
+  fn from(e: toml::de::Error) -> Self {
+    ConfigError::Parse(e)
+  }
+}
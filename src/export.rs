@@ -0,0 +1,317 @@
+use std::{
+  fmt,
+  fs::File,
+  io::{self, BufWriter, Write},
+  path::Path,
+};
+
+use serde::Serialize;
+
+use crate::config::SimConfig;
+use crate::metrics::{Metrics, TickMetrics};
+use crate::state::State;
+
+/// One flat row of metrics, with a column per value so it fits in a table.
+#[derive(Serialize)]
+pub struct TickRecord {
+  tick: u64,
+  population: usize,
+  mean_individual_error: Option<f64>,
+  min_individual_error: Option<f64>,
+  max_individual_error: Option<f64>,
+  liars: usize,
+  mean_honest_error: Option<f64>,
+  mean_liar_error: Option<f64>,
+  trust_in_liars: Option<f64>,
+  trust_in_honest: Option<f64>,
+  collective_error: Option<f64>,
+  mean_entropy: Option<f64>,
+  mean_trust: Option<f64>,
+  mean_belief_age: Option<f64>,
+  first_hand_share: f64,
+  hearsay_share: f64,
+  first_hand_error: Option<f64>,
+  hearsay_error: Option<f64>,
+  guess_error: Option<f64>,
+  mean_hops: Option<f64>,
+  conversations: u64,
+  cells_shared: u64,
+  total_cells_shared: u64,
+  cells_lied: u64,
+  cells_dropped: u64,
+  talk_declined: u64,
+  messages_in_flight: usize,
+  hunger_mean: Option<f64>,
+  hunger_std_dev: Option<f64>,
+  hunger_min: Option<f64>,
+  hunger_max: Option<f64>,
+  thirst_mean: Option<f64>,
+  thirst_std_dev: Option<f64>,
+  thirst_min: Option<f64>,
+  thirst_max: Option<f64>,
+}
+
+impl From<&TickMetrics> for TickRecord {
+  fn from(m: &TickMetrics) -> Self {
+    let errors = m.people.iter().map(|p| p.error);
+    let non_empty = |v: f64| if m.people.is_empty() { None } else { Some(v) };
+
+    Self {
+      tick: m.tick,
+      population: m.population,
+      mean_individual_error: m.mean_individual_error,
+      min_individual_error: non_empty(
+        errors.clone().fold(f64::INFINITY, f64::min),
+      ),
+      max_individual_error: non_empty(errors.fold(f64::NEG_INFINITY, f64::max)),
+      liars: m.liars,
+      mean_honest_error: m.mean_honest_error,
+      mean_liar_error: m.mean_liar_error,
+      trust_in_liars: m.trust_in_liars,
+      trust_in_honest: m.trust_in_honest,
+      collective_error: m.collective_error,
+      mean_entropy: m.mean_entropy,
+      mean_trust: m.mean_trust,
+      mean_belief_age: m.mean_belief_age,
+      first_hand_share: m.provenance.first_hand_share,
+      hearsay_share: m.provenance.hearsay_share,
+      first_hand_error: m.provenance.first_hand_error,
+      hearsay_error: m.provenance.hearsay_error,
+      guess_error: m.provenance.guess_error,
+      mean_hops: m.provenance.mean_hops,
+      conversations: m.traffic.conversations,
+      cells_shared: m.traffic.cells,
+      total_cells_shared: m.total_traffic.cells,
+      cells_lied: m.traffic.lies,
+      cells_dropped: m.traffic.dropped,
+      talk_declined: m.traffic.declined,
+      messages_in_flight: m.messages_in_flight,
+      hunger_mean: m.hunger.as_ref().map(|d| d.mean),
+      hunger_std_dev: m.hunger.as_ref().map(|d| d.std_dev),
+      hunger_min: m.hunger.as_ref().map(|d| d.min),
+      hunger_max: m.hunger.as_ref().map(|d| d.max),
+      thirst_mean: m.thirst.as_ref().map(|d| d.mean),
+      thirst_std_dev: m.thirst.as_ref().map(|d| d.std_dev),
+      thirst_min: m.thirst.as_ref().map(|d| d.min),
+      thirst_max: m.thirst.as_ref().map(|d| d.max),
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct RunHeader<'a> {
+  seed: u64,
+  config: &'a SimConfig,
+}
+
+pub trait MetricsExporter {
+  fn export(&mut self, metrics: &TickMetrics) -> Result<(), ExportError>;
+
+  fn finish(&mut self) -> Result<(), ExportError>;
+}
+
+/// Picks an exporter from the file extension: `.csv`, `.jsonl` or `.json`.
+pub fn create(
+  path: &Path,
+  state: &State,
+) -> Result<Box<dyn MetricsExporter>, ExportError> {
+  let extension = path
+    .extension()
+    .and_then(|e| e.to_str())
+    .map(|e| e.to_ascii_lowercase());
+
+  let header = RunHeader {
+    seed: state.seed,
+    config: &state.config,
+  };
+
+  match extension.as_deref() {
+    Some("csv") => {
+      Ok(Box::new(CsvExporter::new(File::create(path)?, &header)?))
+    }
+    Some("jsonl") => Ok(Box::new(JsonLinesExporter::new(
+      File::create(path)?,
+      &header,
+    )?)),
+    Some("json") => {
+      Ok(Box::new(JsonExporter::new(File::create(path)?, &header)?))
+    }
+    _ => Err(ExportError::UnknownFormat(path.display().to_string())),
+  }
+}
+
+/// Writes the run's seed and parameters as `#` comment lines, followed by a
+/// header row and one row per tick.
+pub struct CsvExporter<W: Write> {
+  writer: csv::Writer<W>,
+}
+
+impl<W: Write> CsvExporter<W> {
+  fn new(mut writer: W, header: &RunHeader) -> Result<Self, ExportError> {
+    writeln!(writer, "# seed = {}", header.seed)?;
+    write_comment(&mut writer, header.config)?;
+
+    Ok(Self {
+      writer: csv::Writer::from_writer(writer),
+    })
+  }
+}
+
+impl<W: Write> MetricsExporter for CsvExporter<W> {
+  fn export(&mut self, metrics: &TickMetrics) -> Result<(), ExportError> {
+    self.writer.serialize(TickRecord::from(metrics))?;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<(), ExportError> {
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+/// Writes the run's seed and parameters as the first line, followed by one
+/// record per tick.
+pub struct JsonLinesExporter<W: Write> {
+  writer: BufWriter<W>,
+}
+
+impl<W: Write> JsonLinesExporter<W> {
+  fn new(writer: W, header: &RunHeader) -> Result<Self, ExportError> {
+    let mut writer = BufWriter::new(writer);
+    serde_json::to_writer(&mut writer, header)?;
+    writeln!(writer)?;
+    Ok(Self { writer })
+  }
+}
+
+impl<W: Write> MetricsExporter for JsonLinesExporter<W> {
+  fn export(&mut self, metrics: &TickMetrics) -> Result<(), ExportError> {
+    serde_json::to_writer(&mut self.writer, &TickRecord::from(metrics))?;
+    writeln!(self.writer)?;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<(), ExportError> {
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+/// Keeps the full metrics, including every person, and writes them as a
+/// single document when finished.
+pub struct JsonExporter<W: Write> {
+  writer: BufWriter<W>,
+  seed: u64,
+  config: SimConfig,
+  metrics: Metrics,
+}
+
+impl<W: Write> JsonExporter<W> {
+  fn new(writer: W, header: &RunHeader) -> Result<Self, ExportError> {
+    Ok(Self {
+      writer: BufWriter::new(writer),
+      seed: header.seed,
+      config: header.config.clone(),
+      metrics: Metrics::default(),
+    })
+  }
+}
+
+impl<W: Write> MetricsExporter for JsonExporter<W> {
+  fn export(&mut self, metrics: &TickMetrics) -> Result<(), ExportError> {
+    self.metrics.push(metrics.clone());
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<(), ExportError> {
+    #[derive(Serialize)]
+    struct Document<'a> {
+      seed: u64,
+      config: &'a SimConfig,
+      ticks: &'a [TickMetrics],
+    }
+
+    serde_json::to_writer(
+      &mut self.writer,
+      &Document {
+        seed: self.seed,
+        config: &self.config,
+        ticks: self.metrics.history(),
+      },
+    )?;
+    self.writer.flush()?;
+    Ok(())
+  }
+}
+
+/// Writes a value as TOML with every line commented out by a `#`.
+pub fn write_comment<W: Write, T: Serialize>(
+  writer: &mut W,
+  value: &T,
+) -> Result<(), ExportError> {
+  for line in toml::to_string(value)?.lines() {
+    writeln!(writer, "{}", format!("# {}", line).trim_end())?;
+  }
+  Ok(())
+}
+
+#[derive(Debug)]
+pub enum ExportError {
+  Io(io::Error),
+  Csv(csv::Error),
+  Json(serde_json::Error),
+  Toml(toml::ser::Error),
+  UnknownFormat(String),
+}
+
+impl fmt::Display for ExportError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ExportError::Io(e) => write!(f, "failed to write metrics: {}", e),
+      ExportError::Csv(e) => write!(f, "failed to write CSV metrics: {}", e),
+      ExportError::Json(e) => write!(f, "failed to write JSON metrics: {}", e),
+      ExportError::Toml(e) => write!(f, "failed to write run header: {}", e),
+      ExportError::UnknownFormat(path) => write!(
+        f,
+        "don't know how to export metrics to {}, expected .csv, .jsonl or .json",
+        path
+      ),
+    }
+  }
+}
+
+impl std::error::Error for ExportError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      ExportError::Io(e) => Some(e),
+      ExportError::Csv(e) => Some(e),
+      ExportError::Json(e) => Some(e),
+      ExportError::Toml(e) => Some(e),
+      ExportError::UnknownFormat(_) => None,
+    }
+  }
+}
+
+impl From<io::Error> for ExportError {
+  fn from(e: io::Error) -> Self {
+    ExportError::Io(e)
+  }
+}
+
+impl From<csv::Error> for ExportError {
+  fn from(e: csv::Error) -> Self {
+    ExportError::Csv(e)
+  }
+}
+
+impl From<serde_json::Error> for ExportError {
+  fn from(e: serde_json::Error) -> Self {
+    ExportError::Json(e)
+  }
+}
+
+impl From<toml::ser::Error> for ExportError {
+  fn from(e: toml::ser::Error) -> Self {
+    ExportError::Toml(e)
+  }
+}
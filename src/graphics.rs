@@ -1,4 +1,4 @@
-use std::ops::Mul;
+use std::{fmt, ops::Mul, str::FromStr};
 
 use image::{GenericImage, ImageBuffer, Rgb, RgbImage, SubImage};
 
@@ -31,6 +31,82 @@ impl ImageGrid {
   }
 }
 
+/// One of the views drawn side by side by `State::draw`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Panel {
+  Map,
+  People,
+  /// The selected person's beliefs.
+  Brain,
+  /// Where the selected person's beliefs came from: green for first-hand,
+  /// blue for hearsay.
+  Provenance,
+  /// How sure the selected person is of their beliefs, brighter for surer.
+  Certainty,
+  /// Where the selected person would most like to go.
+  Favorability,
+  /// Cells the selected person is wrong about.
+  Error,
+  /// Cells the majority of people are wrong about.
+  CollectiveError,
+}
+
+impl Panel {
+  /// Every panel, in the order `State::draw` lays them out.
+  pub const ALL: [Panel; 6] = [
+    Panel::Map,
+    Panel::Brain,
+    Panel::Error,
+    Panel::People,
+    Panel::Favorability,
+    Panel::CollectiveError,
+  ];
+
+  const VARIANTS: [Panel; 8] = [
+    Panel::Map,
+    Panel::People,
+    Panel::Brain,
+    Panel::Provenance,
+    Panel::Certainty,
+    Panel::Favorability,
+    Panel::Error,
+    Panel::CollectiveError,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Panel::Map => "map",
+      Panel::People => "people",
+      Panel::Brain => "brain",
+      Panel::Provenance => "provenance",
+      Panel::Certainty => "certainty",
+      Panel::Favorability => "favorability",
+      Panel::Error => "error",
+      Panel::CollectiveError => "collective-error",
+    }
+  }
+}
+
+impl fmt::Display for Panel {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.name())
+  }
+}
+
+impl FromStr for Panel {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    Panel::VARIANTS
+      .into_iter()
+      .find(|p| p.name() == s)
+      .ok_or_else(|| {
+        let names: Vec<_> = Panel::VARIANTS.iter().map(|p| p.name()).collect();
+        format!("unknown panel {}, expected one of {}", s, names.join(", "))
+      })
+  }
+}
+
 pub struct Color([f64; 3]);
 
 impl Mul<f64> for Color {
@@ -74,7 +150,7 @@ impl ColorMixer {
     }
   }
 
-  fn mix(&mut self, color: &Color) {
+  pub fn mix(&mut self, color: &Color) {
     for (v, c) in self.mixer.iter_mut().zip(color.0) {
       *v += c;
     }
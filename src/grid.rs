@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::ops::{Index, IndexMut};
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+/// How a `Grid` lays out its cells in memory.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Layout {
+  /// Every cell stored, one after another.
+  Dense,
+  /// Only the cells that have been written to are stored.
+  Sparse,
+  /// Cells stored in square tiles of `size` cells a side. Every tile starts
+  /// out as a shared copy of the default and only gets its own storage when
+  /// first written to.
+  Tiled { size: usize },
+}
+
+/// A value for every cell of the map, stored as laid out by a `Layout`.
+///
+/// Cells that were never written to read as the default, and writing through
+/// `IndexMut` stores the cell, so reads should go through `Index` and writes
+/// that may leave a cell at the default through `set` or `update`.
+#[derive(Clone, Serialize, Deserialize)]
+pub enum Grid<T> {
+  Dense {
+    default: T,
+    cells: Array2<T>,
+  },
+  Sparse {
+    dim: (usize, usize),
+    default: T,
+    /// Stored cells by `x * height + y`.
+    cells: BTreeMap<usize, T>,
+  },
+  Tiled {
+    dim: (usize, usize),
+    size: usize,
+    default: T,
+    /// Tiles in the same order as cells, `None` while still the default.
+    tiles: Vec<Option<Array2<T>>>,
+  },
+}
+
+impl<T: Clone> Grid<T> {
+  pub fn from_elem(dim: (usize, usize), default: T, layout: Layout) -> Self {
+    match layout {
+      Layout::Dense => Grid::Dense {
+        cells: Array2::from_elem(dim, default.clone()),
+        default,
+      },
+      Layout::Sparse => Grid::Sparse {
+        dim,
+        default,
+        cells: BTreeMap::new(),
+      },
+      Layout::Tiled { size } => Grid::Tiled {
+        dim,
+        size,
+        default,
+        tiles: vec![None; dim.0.div_ceil(size) * dim.1.div_ceil(size)],
+      },
+    }
+  }
+
+  /// A dense grid holding `cells`, cleared back to `default`.
+  pub fn from_array(cells: Array2<T>, default: T) -> Self {
+    Grid::Dense { default, cells }
+  }
+
+  pub fn dim(&self) -> (usize, usize) {
+    match self {
+      Grid::Dense { cells, .. } => cells.dim(),
+      Grid::Sparse { dim, .. } | Grid::Tiled { dim, .. } => *dim,
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    let (width, height) = self.dim();
+    width * height
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// Puts a cell back to the default, freeing it if the layout allows.
+  pub fn clear(&mut self, i: (usize, usize)) {
+    match self {
+      Grid::Dense { default, cells } => cells[i] = default.clone(),
+      Grid::Sparse { dim, cells, .. } => {
+        cells.remove(&(i.0 * dim.1 + i.1));
+      }
+      Grid::Tiled {
+        dim,
+        size,
+        default,
+        tiles,
+      } => {
+        let t = (i.0 / *size) * dim.1.div_ceil(*size) + i.1 / *size;
+        if let Some(tile) = &mut tiles[t] {
+          tile[(i.0 % *size, i.1 % *size)] = default.clone();
+        }
+      }
+    }
+  }
+
+  /// Cells with storage of their own, counting every cell of a stored tile.
+  pub fn stored(&self) -> usize {
+    match self {
+      Grid::Dense { cells, .. } => cells.len(),
+      Grid::Sparse { cells, .. } => cells.len(),
+      Grid::Tiled { size, tiles, .. } => {
+        tiles.iter().filter(|t| t.is_some()).count() * size * size
+      }
+    }
+  }
+
+  /// What cells that were never written to read as.
+  pub fn default(&self) -> &T {
+    match self {
+      Grid::Dense { default, .. }
+      | Grid::Sparse { default, .. }
+      | Grid::Tiled { default, .. } => default,
+    }
+  }
+
+  /// Calls `f` with every cell and its value, row by row.
+  pub fn for_each<F: FnMut((usize, usize), &T)>(&self, mut f: F) {
+    match self {
+      Grid::Dense { cells, .. } => {
+        cells.indexed_iter().for_each(|(i, v)| f(i, v))
+      }
+      _ => {
+        let (width, height) = self.dim();
+        for x in 0..width {
+          for y in 0..height {
+            f((x, y), &self[(x, y)]);
+          }
+        }
+      }
+    }
+  }
+
+  /// Applies `f` to every value, keeping the layout.
+  pub fn map<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Grid<U> {
+    match self {
+      Grid::Dense { default, cells } => Grid::Dense {
+        default: f(default),
+        cells: cells.map(f),
+      },
+      Grid::Sparse {
+        dim,
+        default,
+        cells,
+      } => Grid::Sparse {
+        dim: *dim,
+        default: f(default),
+        cells: cells.iter().map(|(k, v)| (*k, f(v))).collect(),
+      },
+      Grid::Tiled {
+        dim,
+        size,
+        default,
+        tiles,
+      } => Grid::Tiled {
+        dim: *dim,
+        size: *size,
+        default: f(default),
+        tiles: tiles
+          .iter()
+          .map(|tile| tile.as_ref().map(|tile| tile.map(&mut f)))
+          .collect(),
+      },
+    }
+  }
+
+  /// Applies `f` to every cell into a dense array.
+  pub fn to_array<U, F: FnMut(&T) -> U>(&self, mut f: F) -> Array2<U> {
+    match self {
+      Grid::Dense { cells, .. } => cells.map(f),
+      _ => Array2::from_shape_fn(self.dim(), |i| f(&self[i])),
+    }
+  }
+}
+
+impl<T: Clone + PartialEq> Grid<T> {
+  pub fn is_default(&self, i: (usize, usize)) -> bool {
+    self[i] == *self.default()
+  }
+
+  /// Writes a cell, only storing it if it's not the default.
+  pub fn set(&mut self, i: (usize, usize), value: T) {
+    if value == *self.default() {
+      self.clear(i);
+    } else {
+      self[i] = value;
+    }
+  }
+
+  /// Applies `f` to a cell, storing the result with `set`.
+  pub fn update<F: FnOnce(&mut T)>(&mut self, i: (usize, usize), f: F) {
+    let mut value = self[i].clone();
+    f(&mut value);
+    self.set(i, value);
+  }
+
+  /// Calls `f` with every cell that's stored and isn't at the default, row
+  /// by row, so `f` must leave the default as it is. Skipping the default in
+  /// every layout, not just where it isn't stored, keeps layouts from
+  /// drifting apart when `f` leaves it nearly but not exactly as it was.
+  pub fn for_each_stored_mut<F: FnMut((usize, usize), &mut T)>(
+    &mut self,
+    mut f: F,
+  ) {
+    match self {
+      Grid::Dense { default, cells } => {
+        for (i, v) in cells.indexed_iter_mut() {
+          if *v != *default {
+            f(i, v);
+          }
+        }
+      }
+      Grid::Sparse {
+        dim,
+        default,
+        cells,
+      } => {
+        for (k, v) in cells.iter_mut() {
+          if *v != *default {
+            f((k / dim.1, k % dim.1), v);
+          }
+        }
+      }
+      Grid::Tiled {
+        dim,
+        size,
+        default,
+        tiles,
+      } => {
+        let per_row = dim.1.div_ceil(*size);
+        for (t, tile) in tiles.iter_mut().enumerate() {
+          let Some(tile) = tile else { continue };
+          let (tx, ty) = ((t / per_row) * *size, (t % per_row) * *size);
+          for ((x, y), v) in tile.indexed_iter_mut() {
+            if tx + x < dim.0 && ty + y < dim.1 && *v != *default {
+              f((tx + x, ty + y), v);
+            }
+          }
+        }
+      }
+    }
+  }
+
+  /// Calls `f` with every cell, row by row, storing the result with `set`.
+  pub fn for_each_mut<F: FnMut((usize, usize), &mut T)>(&mut self, mut f: F) {
+    match self {
+      Grid::Dense { cells, .. } => {
+        cells.indexed_iter_mut().for_each(|(i, v)| f(i, v))
+      }
+      _ => {
+        let (width, height) = self.dim();
+        for x in 0..width {
+          for y in 0..height {
+            self.update((x, y), |v| f((x, y), v));
+          }
+        }
+      }
+    }
+  }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+  type Output = T;
+
+  fn index(&self, i: (usize, usize)) -> &T {
+    match self {
+      Grid::Dense { cells, .. } => &cells[i],
+      Grid::Sparse {
+        dim,
+        default,
+        cells,
+      } => {
+        assert!(i.0 < dim.0 && i.1 < dim.1, "cell {:?} out of bounds", i);
+        cells.get(&(i.0 * dim.1 + i.1)).unwrap_or(default)
+      }
+      Grid::Tiled {
+        dim,
+        size,
+        default,
+        tiles,
+      } => {
+        assert!(i.0 < dim.0 && i.1 < dim.1, "cell {:?} out of bounds", i);
+        let t = (i.0 / size) * dim.1.div_ceil(*size) + i.1 / size;
+        match &tiles[t] {
+          Some(tile) => &tile[(i.0 % size, i.1 % size)],
+          None => default,
+        }
+      }
+    }
+  }
+}
+
+impl<T: Clone> IndexMut<(usize, usize)> for Grid<T> {
+  fn index_mut(&mut self, i: (usize, usize)) -> &mut T {
+    match self {
+      Grid::Dense { cells, .. } => &mut cells[i],
+      Grid::Sparse {
+        dim,
+        default,
+        cells,
+      } => {
+        assert!(i.0 < dim.0 && i.1 < dim.1, "cell {:?} out of bounds", i);
+        cells
+          .entry(i.0 * dim.1 + i.1)
+          .or_insert_with(|| default.clone())
+      }
+      Grid::Tiled {
+        dim,
+        size,
+        default,
+        tiles,
+      } => {
+        assert!(i.0 < dim.0 && i.1 < dim.1, "cell {:?} out of bounds", i);
+        let t = (i.0 / *size) * dim.1.div_ceil(*size) + i.1 / *size;
+        let tile = tiles[t].get_or_insert_with(|| {
+          Array2::from_elem((*size, *size), default.clone())
+        });
+        &mut tile[(i.0 % *size, i.1 % *size)]
+      }
+    }
+  }
+}
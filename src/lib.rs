@@ -0,0 +1,35 @@
+//! Simulation of people foraging on a shared map while forming and exchanging
+//! beliefs about where the resources are.
+//!
+//! Build a world with [`State::gen`], advance it with [`State::update`], look
+//! at the people and their beliefs through [`State::people`], and render a
+//! frame with [`State::draw`].
+
+pub mod belief;
+pub mod channel;
+pub mod config;
+pub mod export;
+pub mod graphics;
+pub mod grid;
+pub mod map;
+pub mod message;
+pub mod metrics;
+mod ndarray_pad;
+pub mod network;
+pub mod person;
+pub mod provenance;
+pub mod record;
+pub mod resource;
+pub mod snapshot;
+pub mod state;
+pub mod sweep;
+pub mod systems;
+pub mod trust;
+
+pub use belief::{Belief, Beliefs};
+pub use config::SimConfig;
+pub use map::{Biome, Map};
+pub use person::{Brain, Needs, Person, ResourceProbability};
+pub use resource::Resource;
+pub use state::State;
+pub use systems::{Pipeline, System};
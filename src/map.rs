@@ -5,18 +5,29 @@ use image::SubImage;
 use ndarray::Array2;
 use rand::distributions::WeightedIndex;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::config::NUM_BIOMES;
+use crate::config::MapConfig;
 use crate::resource::Resource;
 
-pub(crate) struct Map {
+#[derive(Serialize, Deserialize)]
+pub struct Map {
   pub(crate) resources: Array2<Resource>,
   pub(crate) biomes: Biomes,
+  /// The biome of every cell, looked up once rather than on every access.
+  pub(crate) biome_map: Array2<Biome>,
 }
 
 impl Map {
-  pub(crate) fn gen<R: Rng>(rng: &mut R, width: usize, height: usize) -> Self {
-    let biomes: Vec<_> = (0..NUM_BIOMES)
+  pub fn gen<R: Rng>(rng: &mut R, config: &MapConfig) -> Self {
+    let MapConfig {
+      width,
+      height,
+      num_biomes,
+      ..
+    } = *config;
+
+    let biomes: Vec<_> = (0..num_biomes)
       .map(|_| {
         (
           rng.gen_range(0..width),
@@ -27,33 +38,53 @@ impl Map {
       .collect();
 
     let biomes = Biomes(biomes);
+    let biome_map =
+      Array2::from_shape_fn((width, height), |(x, y)| *biomes.get_biome(x, y));
     Self {
       resources: Array2::from_shape_fn((width, height), |(x, y)| {
-        biomes.get_biome(x, y).gen_resource(rng)
+        biome_map[(x, y)].gen_resource(rng)
       }),
       biomes,
+      biome_map,
     }
   }
 
-  pub(crate) fn width(&self) -> usize {
+  pub fn width(&self) -> usize {
     self.resources.shape()[0]
   }
 
-  pub(crate) fn height(&self) -> usize {
+  pub fn height(&self) -> usize {
     self.resources.shape()[1]
   }
 
-  pub(crate) fn draw(&self, img: &mut SubImage<&mut RgbImage>) {
+  pub fn resource(&self, x: usize, y: usize) -> Resource {
+    self.resources[(x, y)]
+  }
+
+  pub fn resources(&self) -> &Array2<Resource> {
+    &self.resources
+  }
+
+  pub fn resources_mut(&mut self) -> &mut Array2<Resource> {
+    &mut self.resources
+  }
+
+  pub fn biome(&self, x: usize, y: usize) -> &Biome {
+    &self.biome_map[(x, y)]
+  }
+
+  pub fn draw(&self, img: &mut SubImage<&mut RgbImage>) {
     for ((x, y), v) in self.resources.indexed_iter() {
       img.put_pixel(x as u32, y as u32, v.color().into());
     }
   }
 }
 
-pub(crate) struct Biomes(Vec<(usize, usize, Biome)>);
+#[derive(Serialize, Deserialize)]
+pub struct Biomes(Vec<(usize, usize, Biome)>);
 
 impl Biomes {
-  pub(crate) fn get_biome(&self, x: usize, y: usize) -> &Biome {
+  pub fn get_biome(&self, x: usize, y: usize) -> &Biome {
     self
       .0
       .iter()
@@ -70,8 +101,8 @@ impl Biomes {
   }
 }
 
-#[derive(Ordinalize)]
-pub(crate) enum Biome {
+#[derive(Clone, Copy, Ordinalize, Serialize, Deserialize)]
+pub enum Biome {
   Plains,
   Lake,
   Mountain,
@@ -79,11 +110,11 @@ pub(crate) enum Biome {
 }
 
 impl Biome {
-  pub(crate) fn gen<R: Rng>(rng: &mut R) -> Self {
+  pub fn gen<R: Rng>(rng: &mut R) -> Self {
     Biome::from_ordinal(rng.gen_range(0..Biome::variant_count() as i8)).unwrap()
   }
 
-  pub(crate) fn gen_resource<R: Rng>(&self, rng: &mut R) -> Resource {
+  pub fn gen_resource<R: Rng>(&self, rng: &mut R) -> Resource {
     let weights = match self {
       Biome::Plains => [0.2, 0.75, 0.0, 0.05, 0.0],
       Biome::Lake => [0.0, 0.0, 1.0, 0.0, 0.0],
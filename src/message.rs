@@ -0,0 +1,156 @@
+use std::ops::AddAssign;
+
+use ndarray::indices;
+use rand::{prelude::*, seq::index};
+use serde::{Deserialize, Serialize};
+
+use crate::config::{CommunicationConfig, Lie, ShareSelection};
+use crate::person::{Person, ResourceProbability, Role};
+use crate::provenance::Provenance;
+use crate::resource::Resource;
+
+/// What one person tells another in a conversation: their beliefs about some
+/// of the cells of the map.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Message {
+  pub from: usize,
+  pub cells: Vec<SharedBelief>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct SharedBelief {
+  pub x: usize,
+  pub y: usize,
+  pub belief: ResourceProbability,
+  /// How much evidence the belief rests on, if the speaker keeps count.
+  pub concentration: Option<f64>,
+  /// Tick the belief was last observed first-hand.
+  pub observed: Option<u64>,
+  /// Where the speaker's belief came from.
+  pub provenance: Provenance,
+}
+
+impl Message {
+  /// Picks which cells `speaker` shares, all of them if the bandwidth isn't
+  /// limited, distorting them with `lie` if they're a liar. Cells the speaker
+  /// still believes the default about have nothing to say and are left out.
+  pub fn compose<R: Rng>(
+    rng: &mut R,
+    speaker: &Person,
+    config: &CommunicationConfig,
+    lie: Lie,
+  ) -> Self {
+    let beliefs = &speaker.brain.beliefs;
+    let cells = match config.bandwidth {
+      Some(bandwidth) if bandwidth < beliefs.len() => {
+        select(rng, speaker, config.selection, bandwidth)
+      }
+      _ => indices(beliefs.dim())
+        .into_iter()
+        .filter(|i| !beliefs.is_default(*i))
+        .collect(),
+    };
+
+    let lie = match speaker.role {
+      Role::Honest => None,
+      Role::Liar => Some(lie),
+    };
+    let cells = cells
+      .into_iter()
+      .map(|(x, y)| {
+        let mut belief = beliefs.get((x, y)).distribution();
+        let concentration = beliefs.get((x, y)).concentration();
+        if let Some(lie) = lie {
+          belief.lie(lie);
+        }
+        SharedBelief {
+          x,
+          y,
+          belief,
+          concentration,
+          observed: speaker.brain.observed[(x, y)].tick(),
+          provenance: speaker.brain.provenance[(x, y)].unpack(),
+        }
+      })
+      .collect();
+
+    Self {
+      from: speaker.id,
+      cells,
+    }
+  }
+}
+
+fn select<R: Rng>(
+  rng: &mut R,
+  speaker: &Person,
+  selection: ShareSelection,
+  bandwidth: usize,
+) -> Vec<(usize, usize)> {
+  let beliefs = &speaker.brain.beliefs;
+  if selection == ShareSelection::Random {
+    let cells: Vec<_> = indices(beliefs.dim())
+      .into_iter()
+      .filter(|i| !beliefs.is_default(*i))
+      .collect();
+    return index::sample(rng, cells.len(), bandwidth.min(cells.len()))
+      .into_iter()
+      .map(|i| cells[i])
+      .collect();
+  }
+
+  let mut scored = Vec::with_capacity(beliefs.len());
+  beliefs.for_each(|(x, y), b| {
+    if beliefs.is_default((x, y)) {
+      return;
+    }
+    let score = match selection {
+      ShareSelection::Confident => b.probability(b.most_likely()),
+      ShareSelection::Nearby => {
+        -((speaker.x as f64 - x as f64).powi(2)
+          + (speaker.y as f64 - y as f64).powi(2))
+      }
+      ShareSelection::Resources => {
+        b.probability(Resource::Food) + b.probability(Resource::Water)
+      }
+      ShareSelection::Random => unreachable!(),
+    };
+    scored.push((score, (x, y)));
+  });
+
+  if bandwidth == 0 {
+    return vec![];
+  }
+  if scored.len() <= bandwidth {
+    return scored.into_iter().map(|(_, i)| i).collect();
+  }
+  scored.select_nth_unstable_by(bandwidth - 1, |a, b| {
+    b.0.partial_cmp(&a.0).unwrap()
+  });
+  scored.truncate(bandwidth);
+  scored.into_iter().map(|(_, i)| i).collect()
+}
+
+/// How much was said, either in one tick or over a whole run.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct Traffic {
+  pub conversations: u64,
+  /// Cells shared, counting both directions of a conversation.
+  pub cells: u64,
+  /// Cells shared by liars.
+  pub lies: u64,
+  /// Cells lost to channel noise.
+  pub dropped: u64,
+  /// People who would rather forage than talk.
+  pub declined: u64,
+}
+
+impl AddAssign for Traffic {
+  fn add_assign(&mut self, other: Self) {
+    self.conversations += other.conversations;
+    self.cells += other.cells;
+    self.lies += other.lies;
+    self.dropped += other.dropped;
+    self.declined += other.declined;
+  }
+}
@@ -0,0 +1,284 @@
+use std::io::Write;
+use std::ops::Add;
+
+use ndarray::Array2;
+use serde::Serialize;
+
+use crate::config::BeliefModel;
+use crate::person::Brain;
+use crate::provenance::Source;
+use crate::resource::Resource;
+
+use crate::message::Traffic;
+use crate::state::{error_rate, State};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TickMetrics {
+  pub tick: u64,
+  pub population: usize,
+  pub people: Vec<PersonMetrics>,
+  pub mean_individual_error: Option<f64>,
+  pub liars: usize,
+  /// Mean error of the people who don't lie, to compare against runs without
+  /// liars.
+  pub mean_honest_error: Option<f64>,
+  pub mean_liar_error: Option<f64>,
+  /// Mean trust honest people have learned in liars.
+  pub trust_in_liars: Option<f64>,
+  /// Mean trust honest people have learned in other honest people.
+  pub trust_in_honest: Option<f64>,
+  pub collective_error: Option<f64>,
+  pub mean_entropy: Option<f64>,
+  pub mean_trust: Option<f64>,
+  pub mean_belief_age: Option<f64>,
+  /// Pooled over everyone's beliefs.
+  pub provenance: ProvenanceMetrics,
+  pub traffic: Traffic,
+  pub total_traffic: Traffic,
+  /// Messages sent but still held back by latency.
+  pub messages_in_flight: usize,
+  pub hunger: Option<Distribution>,
+  pub thirst: Option<Distribution>,
+}
+
+impl TickMetrics {
+  pub fn collect(state: &State) -> Self {
+    let provenance: Vec<_> = state
+      .people
+      .iter()
+      .map(|p| ProvenanceCounts::of(&p.brain, &state.map.resources))
+      .collect();
+
+    let people: Vec<_> = state
+      .people
+      .iter()
+      .zip(provenance.iter())
+      .map(|(p, provenance)| PersonMetrics {
+        id: p.id,
+        liar: p.is_liar(),
+        belief: p.brain.model(),
+        error: error_rate(&p.brain.plurality_map(), &state.map.resources),
+        entropy: p.brain.mean_entropy(),
+        hunger: p.needs.hunger,
+        thirst: p.needs.thirst,
+        mean_trust: mean(p.trust.scores().map(|(_, score)| score)),
+        mean_belief_age: p.brain.mean_age(state.tick),
+        provenance: (*provenance).into(),
+      })
+      .collect();
+
+    let liars: Vec<_> = state
+      .people
+      .iter()
+      .filter(|p| p.is_liar())
+      .map(|p| p.id)
+      .collect();
+    let learned_trust = |in_liars: bool| {
+      mean(
+        state
+          .people
+          .iter()
+          .filter(|p| !p.is_liar())
+          .flat_map(|p| p.trust.scores())
+          .filter(|(id, _)| liars.contains(id) == in_liars)
+          .map(|(_, score)| score),
+      )
+    };
+
+    let collective_error = if people.is_empty() {
+      None
+    } else {
+      Some(error_rate(&state.collective_view(), &state.map.resources))
+    };
+
+    Self {
+      tick: state.tick,
+      population: people.len(),
+      mean_individual_error: mean(people.iter().map(|p| p.error)),
+      liars: liars.len(),
+      mean_honest_error: mean(
+        people.iter().filter(|p| !p.liar).map(|p| p.error),
+      ),
+      mean_liar_error: mean(people.iter().filter(|p| p.liar).map(|p| p.error)),
+      trust_in_liars: learned_trust(true),
+      trust_in_honest: learned_trust(false),
+      collective_error,
+      mean_entropy: mean(people.iter().map(|p| p.entropy)),
+      mean_trust: mean(people.iter().filter_map(|p| p.mean_trust)),
+      mean_belief_age: mean(people.iter().filter_map(|p| p.mean_belief_age)),
+      provenance: provenance
+        .into_iter()
+        .fold(ProvenanceCounts::default(), Add::add)
+        .into(),
+      traffic: state.traffic,
+      total_traffic: state.total_traffic,
+      messages_in_flight: state.channel.in_flight(),
+      hunger: Distribution::of(people.iter().map(|p| p.hunger as f64)),
+      thirst: Distribution::of(people.iter().map(|p| p.thirst as f64)),
+      people,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PersonMetrics {
+  pub id: usize,
+  pub liar: bool,
+  pub belief: BeliefModel,
+  /// Fraction of cells where the person's most likely belief is wrong.
+  pub error: f64,
+  /// Mean Shannon entropy of the person's beliefs, in bits.
+  pub entropy: f64,
+  pub hunger: u16,
+  pub thirst: u16,
+  /// Mean of the trust the person has learned in others, if any.
+  pub mean_trust: Option<f64>,
+  /// Mean ticks since the person's beliefs were observed, leaving out the
+  /// ones that never were.
+  pub mean_belief_age: Option<f64>,
+  pub provenance: ProvenanceMetrics,
+}
+
+/// How much of the beliefs came mostly from each source, and how accurate
+/// those beliefs are.
+#[derive(Clone, Debug, Serialize)]
+pub struct ProvenanceMetrics {
+  pub first_hand_share: f64,
+  pub hearsay_share: f64,
+  pub guess_share: f64,
+  /// Fraction of first-hand beliefs whose most likely resource is wrong.
+  pub first_hand_error: Option<f64>,
+  pub hearsay_error: Option<f64>,
+  pub guess_error: Option<f64>,
+  /// Mean hops of hearsay beliefs.
+  pub mean_hops: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct ProvenanceCounts {
+  cells: [u64; 3],
+  wrong: [u64; 3],
+  hops: f64,
+}
+
+impl ProvenanceCounts {
+  fn of(brain: &Brain, resources: &Array2<Resource>) -> Self {
+    let mut counts = Self::default();
+    brain.beliefs.for_each(|i, belief| {
+      let (provenance, resource) = (brain.provenance[i].unpack(), resources[i]);
+      let source = match provenance.source() {
+        Source::FirstHand => 0,
+        Source::Hearsay => {
+          counts.hops += provenance.hops as f64;
+          1
+        }
+        Source::Guess => 2,
+      };
+      counts.cells[source] += 1;
+      if belief.most_likely() != resource {
+        counts.wrong[source] += 1;
+      }
+    });
+    counts
+  }
+}
+
+impl Add for ProvenanceCounts {
+  type Output = Self;
+
+  fn add(mut self, other: Self) -> Self {
+    for i in 0..3 {
+      self.cells[i] += other.cells[i];
+      self.wrong[i] += other.wrong[i];
+    }
+    self.hops += other.hops;
+    self
+  }
+}
+
+impl From<ProvenanceCounts> for ProvenanceMetrics {
+  fn from(counts: ProvenanceCounts) -> Self {
+    let total: u64 = counts.cells.iter().sum();
+    let share = |i: usize| counts.cells[i] as f64 / total.max(1) as f64;
+    let error = |i: usize| {
+      if counts.cells[i] == 0 {
+        None
+      } else {
+        Some(counts.wrong[i] as f64 / counts.cells[i] as f64)
+      }
+    };
+
+    Self {
+      first_hand_share: share(0),
+      hearsay_share: share(1),
+      guess_share: share(2),
+      first_hand_error: error(0),
+      hearsay_error: error(1),
+      guess_error: error(2),
+      mean_hops: if counts.cells[1] == 0 {
+        None
+      } else {
+        Some(counts.hops / counts.cells[1] as f64)
+      },
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Distribution {
+  pub min: f64,
+  pub max: f64,
+  pub mean: f64,
+  pub std_dev: f64,
+}
+
+impl Distribution {
+  pub fn of<I: Iterator<Item = f64> + Clone>(values: I) -> Option<Self> {
+    let average = mean(values.clone())?;
+    let variance = mean(values.clone().map(|v| (v - average).powi(2)))?;
+
+    Some(Self {
+      min: values.clone().fold(f64::INFINITY, f64::min),
+      max: values.fold(f64::NEG_INFINITY, f64::max),
+      mean: average,
+      std_dev: variance.sqrt(),
+    })
+  }
+}
+
+#[derive(Default)]
+pub struct Metrics {
+  ticks: Vec<TickMetrics>,
+}
+
+impl Metrics {
+  pub fn record(&mut self, state: &State) -> &TickMetrics {
+    self.ticks.push(TickMetrics::collect(state));
+    self.ticks.last().unwrap()
+  }
+
+  pub fn push(&mut self, metrics: TickMetrics) {
+    self.ticks.push(metrics);
+  }
+
+  pub fn history(&self) -> &[TickMetrics] {
+    &self.ticks
+  }
+
+  pub fn latest(&self) -> Option<&TickMetrics> {
+    self.ticks.last()
+  }
+
+  pub fn write_json<W: Write>(&self, writer: W) -> serde_json::Result<()> {
+    serde_json::to_writer(writer, self.history())
+  }
+}
+
+pub fn mean<I: Iterator<Item = f64>>(values: I) -> Option<f64> {
+  let (count, sum) = values.fold((0, 0.0), |(n, s), v| (n + 1, s + v));
+  if count == 0 {
+    None
+  } else {
+    Some(sum / count as f64)
+  }
+}
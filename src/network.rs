@@ -0,0 +1,175 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{ConfigError, NetworkConfig, NetworkKind};
+
+/// Links between people, by id.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Network {
+  Complete,
+  Graph(Vec<(usize, usize)>),
+}
+
+impl Network {
+  pub fn gen<R: Rng>(
+    rng: &mut R,
+    config: &NetworkConfig,
+    count: usize,
+  ) -> Result<Self, ConfigError> {
+    let edges = match (config.kind, &config.path) {
+      (NetworkKind::Complete, _) => return Ok(Network::Complete),
+      (NetworkKind::Ring, _) => ring(count, config.degree),
+      (NetworkKind::WattsStrogatz, _) => {
+        watts_strogatz(rng, count, config.degree, config.rewire)
+      }
+      (NetworkKind::BarabasiAlbert, _) => {
+        barabasi_albert(rng, count, config.links)
+      }
+      (NetworkKind::File, Some(path)) => load_edges(path, count)?,
+      (NetworkKind::File, None) => {
+        return Err(ConfigError::Invalid(
+          "network.path must be set for a file network".to_owned(),
+        ))
+      }
+    };
+    Ok(Network::Graph(edges.into_iter().collect()))
+  }
+
+  /// Every link, `None` if everyone is linked.
+  pub fn edges(&self) -> Option<&[(usize, usize)]> {
+    match self {
+      Network::Complete => None,
+      Network::Graph(edges) => Some(edges),
+    }
+  }
+
+  pub fn linked(&self, a: usize, b: usize) -> bool {
+    match self {
+      Network::Complete => a != b,
+      Network::Graph(edges) => edges.contains(&edge(a, b)),
+    }
+  }
+
+  pub fn degree(&self, id: usize, count: usize) -> usize {
+    match self {
+      Network::Complete => count.saturating_sub(1),
+      Network::Graph(edges) => {
+        edges.iter().filter(|(a, b)| *a == id || *b == id).count()
+      }
+    }
+  }
+}
+
+fn edge(a: usize, b: usize) -> (usize, usize) {
+  (a.min(b), a.max(b))
+}
+
+fn ring(count: usize, degree: usize) -> BTreeSet<(usize, usize)> {
+  let mut edges = BTreeSet::new();
+  for a in 0..count {
+    for offset in 1..=degree / 2 {
+      let b = (a + offset) % count;
+      if a != b {
+        edges.insert(edge(a, b));
+      }
+    }
+  }
+  edges
+}
+
+fn watts_strogatz<R: Rng>(
+  rng: &mut R,
+  count: usize,
+  degree: usize,
+  rewire: f64,
+) -> BTreeSet<(usize, usize)> {
+  let mut edges = ring(count, degree);
+  for (a, b) in ring(count, degree) {
+    if !rng.gen_bool(rewire) {
+      continue;
+    }
+    let candidates: Vec<_> = (0..count)
+      .filter(|c| *c != a && !edges.contains(&edge(a, *c)))
+      .collect();
+    if let Some(c) = candidates.choose(rng) {
+      edges.remove(&(a, b));
+      edges.insert(edge(a, *c));
+    }
+  }
+  edges
+}
+
+fn barabasi_albert<R: Rng>(
+  rng: &mut R,
+  count: usize,
+  links: usize,
+) -> BTreeSet<(usize, usize)> {
+  let mut edges = BTreeSet::new();
+  // Everyone appears once per link they have, so picking from this is
+  // picking in proportion to degree.
+  let mut ends = vec![];
+
+  let seed = (links + 1).min(count);
+  for a in 0..seed {
+    for b in a + 1..seed {
+      edges.insert((a, b));
+      ends.extend([a, b]);
+    }
+  }
+
+  for a in seed..count {
+    let mut targets = BTreeSet::new();
+    while targets.len() < links {
+      targets.insert(*ends.choose(rng).unwrap());
+    }
+    for b in targets {
+      edges.insert(edge(a, b));
+      ends.extend([a, b]);
+    }
+  }
+  edges
+}
+
+fn load_edges(
+  path: &Path,
+  count: usize,
+) -> Result<BTreeSet<(usize, usize)>, ConfigError> {
+  let invalid = |line: usize, message: &str| {
+    ConfigError::Invalid(format!(
+      "{} line {}: {}",
+      path.display(),
+      line + 1,
+      message
+    ))
+  };
+
+  let mut edges = BTreeSet::new();
+  for (i, line) in fs::read_to_string(path)?.lines().enumerate() {
+    let line = line.split('#').next().unwrap().trim();
+    if line.is_empty() {
+      continue;
+    }
+
+    let ids = line
+      .split(|c: char| c.is_whitespace() || c == ',')
+      .filter(|s| !s.is_empty())
+      .map(|s| s.parse::<usize>())
+      .collect::<Result<Vec<_>, _>>()
+      .map_err(|e| invalid(i, &e.to_string()))?;
+    match ids[..] {
+      [a, b] if a < count && b < count && a != b => {
+        edges.insert(edge(a, b));
+      }
+      [_, _] => {
+        return Err(invalid(
+          i,
+          &format!("ids must differ and be less than {}", count),
+        ))
+      }
+      _ => return Err(invalid(i, "expected a pair of person ids")),
+    }
+  }
+  Ok(edges)
+}
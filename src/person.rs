@@ -1,38 +1,110 @@
+use std::f64::consts::PI;
+
 use image::GenericImage;
+use image::Rgb;
 use image::RgbImage;
 use image::SubImage;
 use ndarray::array;
 use ndarray::Array2;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::config::MAX_HUNGER;
-use crate::config::MAX_THIRST;
+use crate::belief::{Belief, Beliefs, BiomePrior, PRIOR_CONCENTRATION};
+use crate::config::{BeliefModel, DecayModel, Lie, MemoryConfig, NeedsConfig};
 use crate::graphics::Color;
+use crate::grid::Grid;
+use crate::map::{Biome, Map};
 use crate::ndarray_pad::ArrayPaddingExt;
 use crate::ndarray_pad::ArrayPaddingKind;
+use crate::provenance::{PackedProvenance, Provenance};
 use crate::resource::Resource;
+use crate::trust::Trust;
 
-pub(crate) struct Person {
+#[derive(Serialize, Deserialize)]
+pub struct Person {
+  pub(crate) id: usize,
+  pub(crate) role: Role,
   pub(crate) brain: Brain,
   pub(crate) needs: Needs,
+  pub(crate) trust: Trust,
+  /// Whether the person's next move goes to a conversation instead.
+  pub(crate) talking: bool,
   pub(crate) x: usize,
   pub(crate) y: usize,
+  /// Where the person started out.
+  pub(crate) home: (usize, usize),
 }
 
 impl Person {
-  pub(crate) fn favorability_map(&self) -> Array2<f64> {
-    let hunger_percent = self.needs.hunger as f64 / MAX_HUNGER as f64;
-    let thirst_percent = self.needs.thirst as f64 / MAX_THIRST as f64;
+  pub fn id(&self) -> usize {
+    self.id
+  }
+
+  pub fn role(&self) -> Role {
+    self.role
+  }
+
+  pub fn is_liar(&self) -> bool {
+    self.role == Role::Liar
+  }
+
+  pub fn position(&self) -> (usize, usize) {
+    (self.x, self.y)
+  }
+
+  /// Straight line distance to another person, in cells.
+  pub fn distance(&self, other: &Person) -> f64 {
+    ((self.x as f64 - other.x as f64).powi(2)
+      + (self.y as f64 - other.y as f64).powi(2))
+    .sqrt()
+  }
+
+  pub fn home(&self) -> (usize, usize) {
+    self.home
+  }
+
+  pub fn is_talking(&self) -> bool {
+    self.talking
+  }
+
+  pub fn needs(&self) -> &Needs {
+    &self.needs
+  }
+
+  pub fn needs_mut(&mut self) -> &mut Needs {
+    &mut self.needs
+  }
+
+  pub fn brain(&self) -> &Brain {
+    &self.brain
+  }
+
+  pub fn brain_mut(&mut self) -> &mut Brain {
+    &mut self.brain
+  }
+
+  pub fn trust(&self) -> &Trust {
+    &self.trust
+  }
+
+  pub fn favorability_map(&self, config: &NeedsConfig) -> Array2<f64> {
+    let hunger_percent = self.needs.hunger as f64 / config.max_hunger as f64;
+    let thirst_percent = self.needs.thirst as f64 / config.max_thirst as f64;
 
     let mut favorability = Array2::from_shape_vec(
-      self.brain.map.raw_dim(),
+      self.brain.beliefs.dim(),
       self
         .brain
-        .map
+        .beliefs
         .map(|b| {
-          b.get(Resource::Food) * hunger_percent
-            + b.get(Resource::Water) * thirst_percent
-            - b.get(Resource::Ghost) * 1.0
+          // Beliefs that keep count discount what rests on little evidence.
+          let weight = b
+            .concentration()
+            .map_or(1.0, |c| c / (c + PRIOR_CONCENTRATION));
+          (b.probability(Resource::Food) * hunger_percent
+            + b.probability(Resource::Water) * thirst_percent
+            - b.probability(Resource::Ghost) * 1.0)
+            * weight
         })
         .pad((2, 2), ArrayPaddingKind::Constant(0.0))
         .windows((5, 5))
@@ -62,43 +134,269 @@ impl Person {
   }
 }
 
-pub(crate) struct Needs {
-  pub(crate) hunger: u16,
-  pub(crate) thirst: u16,
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+  Honest,
+  /// Shares a distorted version of what they believe.
+  Liar,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Needs {
+  pub hunger: u16,
+  pub thirst: u16,
 }
 
 impl Needs {
-  pub(crate) fn met(&self) -> bool {
-    self.hunger < MAX_HUNGER && self.thirst < MAX_THIRST
+  pub fn met(&self, config: &NeedsConfig) -> bool {
+    self.hunger < config.max_hunger && self.thirst < config.max_thirst
+  }
+
+  /// Whether hunger and thirst are both below `fraction` of their maximum.
+  pub fn below(&self, config: &NeedsConfig, fraction: f64) -> bool {
+    (self.hunger as f64) < config.max_hunger as f64 * fraction
+      && (self.thirst as f64) < config.max_thirst as f64 * fraction
   }
 }
 
-#[derive(Clone)]
-pub(crate) struct Brain {
-  pub(crate) map: Array2<ResourceProbability>,
+/// The tick a cell was last observed, in 32 bits to keep brains small.
+/// Ticks past what fits are clamped.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observed(u32);
+
+impl Observed {
+  pub const NEVER: Self = Self(u32::MAX);
+
+  pub fn at(tick: u64) -> Self {
+    Self(tick.min(u32::MAX as u64 - 1) as u32)
+  }
+
+  pub fn tick(self) -> Option<u64> {
+    (self != Self::NEVER).then_some(self.0 as u64)
+  }
+}
+
+impl From<Option<u64>> for Observed {
+  fn from(tick: Option<u64>) -> Self {
+    tick.map_or(Self::NEVER, Self::at)
+  }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Brain {
+  pub(crate) beliefs: Beliefs,
+  /// Tick each belief was last observed first-hand, by anyone.
+  pub(crate) observed: Grid<Observed>,
+  pub(crate) provenance: Grid<PackedProvenance>,
+  /// Learned only with `BiomePrior` decay.
+  pub(crate) prior: BiomePrior,
 }
 
 impl Brain {
-  pub(crate) fn gen<R: Rng>(rng: &mut R, width: usize, height: usize) -> Self {
+  pub fn gen<R: Rng>(
+    rng: &mut R,
+    width: usize,
+    height: usize,
+    model: BeliefModel,
+    memory: &MemoryConfig,
+  ) -> Self {
+    let dim = (width, height);
+    let layout = memory.layout();
     Self {
-      map: Array2::from_shape_simple_fn((width, height), || {
-        ResourceProbability::gen(rng)
-      }),
+      beliefs: Beliefs::gen(rng, dim, model, memory),
+      observed: Grid::from_elem(dim, Observed::NEVER, layout),
+      provenance: Grid::from_elem(dim, PackedProvenance::GUESS, layout),
+      prior: BiomePrior::default(),
+    }
+  }
+
+  pub fn width(&self) -> usize {
+    self.beliefs.dim().0
+  }
+
+  pub fn height(&self) -> usize {
+    self.beliefs.dim().1
+  }
+
+  pub fn model(&self) -> BeliefModel {
+    self.beliefs.model()
+  }
+
+  pub fn beliefs(&self) -> &Beliefs {
+    &self.beliefs
+  }
+
+  pub fn beliefs_mut(&mut self) -> &mut Beliefs {
+    &mut self.beliefs
+  }
+
+  pub fn belief(&self, x: usize, y: usize) -> ResourceProbability {
+    self.beliefs.get((x, y)).distribution()
+  }
+
+  pub fn belief_mut(&mut self, x: usize, y: usize) -> &mut dyn Belief {
+    self.beliefs.get_mut((x, y))
+  }
+
+  pub fn observed(&self, x: usize, y: usize) -> Option<u64> {
+    self.observed[(x, y)].tick()
+  }
+
+  /// How many ticks ago the belief about a cell was observed, `None` if never.
+  pub fn age(&self, x: usize, y: usize, tick: u64) -> Option<u64> {
+    self.observed[(x, y)]
+      .tick()
+      .map(|observed| tick.saturating_sub(observed))
+  }
+
+  /// Mean age of the beliefs that have been observed.
+  pub fn mean_age(&self, tick: u64) -> Option<f64> {
+    let (mut count, mut sum) = (0, 0);
+    self.observed.for_each(|_, observed| {
+      if let Some(observed) = observed.tick() {
+        count += 1;
+        sum += tick.saturating_sub(observed);
+      }
+    });
+    if count == 0 {
+      None
+    } else {
+      Some(sum as f64 / count as f64)
+    }
+  }
+
+  /// Forgets `memory.decay` of every belief, towards even or as `memory.model`
+  /// has it. With an `age_scale`, each belief decays in proportion to its age
+  /// instead, at the full rate once it's `age_scale` ticks old, and guesses
+  /// always decay at the full rate.
+  pub fn decay(
+    &mut self,
+    memory: &MemoryConfig,
+    map: &Map,
+    home: (usize, usize),
+    tick: u64,
+  ) {
+    let Self {
+      beliefs,
+      observed,
+      provenance,
+      prior,
+    } = self;
+    let MemoryConfig {
+      decay, age_scale, ..
+    } = *memory;
+    let aged = |i| match (age_scale, observed[i].tick()) {
+      (Some(scale), Some(observed)) => {
+        (decay * tick.saturating_sub(observed) as f64 / scale).min(1.0)
+      }
+      _ => decay,
+    };
+    let percent = |i: (usize, usize)| match memory.model {
+      DecayModel::Distance => {
+        let distance = ((i.0 as f64 - home.0 as f64).powi(2)
+          + (i.1 as f64 - home.1 as f64).powi(2))
+        .sqrt();
+        (aged(i) * (1.0 + distance / memory.distance_scale)).min(1.0)
+      }
+      _ => aged(i),
+    };
+    // Fading towards even leaves beliefs and provenance at the default as they
+    // are, so only what's stored needs visiting. Fading towards a biome prior
+    // moves even beliefs too, so every one is visited, and those that end up
+    // off the default get stored.
+    match memory.model {
+      DecayModel::BiomePrior => {
+        let priors: Vec<_> =
+          Biome::variants().iter().map(|b| prior.prior(b)).collect();
+        beliefs.for_each_mut(|i, v| {
+          let biome = map.biome(i.0, i.1).ordinal() as usize;
+          v.decay_towards(percent(i), &priors[biome])
+        });
+      }
+      _ => beliefs.for_each_stored_mut(|i, v| v.decay(percent(i))),
+    }
+    provenance.for_each_stored_mut(|i, p| p.update(|p| p.fade(percent(i))));
+
+    if memory.model == DecayModel::Capacity {
+      self.forget_all_but(memory.capacity);
     }
   }
 
-  pub(crate) fn draw(&self, img: &mut SubImage<&mut RgbImage>) {
-    for ((x, y), v) in self.map.indexed_iter() {
-      img.put_pixel(x as u32, y as u32, Color::from(v).into());
+  /// Forgets everything about all but the `capacity` most recently observed
+  /// cells, back to even, freeing them if the storage allows.
+  fn forget_all_but(&mut self, capacity: usize) {
+    let mut remembered = vec![];
+    self.observed.for_each(|i, observed| {
+      if let Some(observed) = observed.tick() {
+        remembered.push((observed, i));
+      }
+    });
+    if remembered.len() <= capacity {
+      return;
     }
+    let forgotten = remembered.len() - capacity;
+    remembered.select_nth_unstable(forgotten);
+    for (_, i) in &remembered[..forgotten] {
+      self.beliefs.clear(*i);
+      self.observed.clear(*i);
+      self.provenance.clear(*i);
+    }
+  }
+
+  /// What the person has learned to expect of each kind of biome.
+  pub fn prior(&self) -> &BiomePrior {
+    &self.prior
+  }
+
+  /// Cells with storage of their own, summed over everything kept about them.
+  pub fn stored(&self) -> usize {
+    self.beliefs.stored() + self.observed.stored() + self.provenance.stored()
+  }
+
+  pub fn provenance(&self, x: usize, y: usize) -> Provenance {
+    self.provenance[(x, y)].unpack()
+  }
+
+  pub fn provenance_map(&self) -> &Grid<PackedProvenance> {
+    &self.provenance
+  }
+
+  pub fn plurality_map(&self) -> Array2<Resource> {
+    self.beliefs.map(|v| v.most_likely())
+  }
+
+  pub fn mean_entropy(&self) -> f64 {
+    let mut total = 0.0;
+    self.beliefs.for_each(|_, v| total += v.entropy());
+    total / self.beliefs.len() as f64
+  }
+
+  pub fn draw(&self, img: &mut SubImage<&mut RgbImage>) {
+    self.beliefs.for_each(|(x, y), v| {
+      img.put_pixel(x as u32, y as u32, Color::from(&v.distribution()).into());
+    });
+  }
+
+  /// Draws how sure each belief is, brighter for surer.
+  pub fn draw_confidence(&self, img: &mut SubImage<&mut RgbImage>) {
+    self.beliefs.for_each(|(x, y), v| {
+      let as_u8 = (v.confidence().clamp(0.0, 1.0) * 255.0) as u8;
+      img.put_pixel(x as u32, y as u32, Rgb([as_u8, as_u8, as_u8]));
+    });
+  }
+
+  pub fn draw_provenance(&self, img: &mut SubImage<&mut RgbImage>) {
+    self.provenance.for_each(|(x, y), v| {
+      img.put_pixel(x as u32, y as u32, v.unpack().color());
+    });
   }
 }
 
-#[derive(Clone)]
-pub(crate) struct ResourceProbability([f64; Resource::variant_count()]);
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceProbability([f64; Resource::variant_count()]);
 
 impl ResourceProbability {
-  pub(crate) fn gen<R: Rng>(rng: &mut R) -> Self {
+  pub fn gen<R: Rng>(rng: &mut R) -> Self {
     let mut inner = [0.0; Resource::variant_count()];
     for v in inner.iter_mut() {
       *v = rng.gen();
@@ -110,7 +408,20 @@ impl ResourceProbability {
     Self(inner)
   }
 
-  pub(crate) fn probable(resource: Resource, p: f64) -> Self {
+  pub fn from_fn<F: FnMut(Resource) -> f64>(mut f: F) -> Self {
+    let mut inner = [0.0; Resource::variant_count()];
+    for (i, v) in inner.iter_mut().enumerate() {
+      *v = f(Resource::from_ordinal(i as i8).unwrap());
+    }
+    Self(inner)
+  }
+
+  /// Every resource equally likely.
+  pub fn even() -> Self {
+    Self([1.0 / Resource::variant_count() as f64; Resource::variant_count()])
+  }
+
+  pub fn probable(resource: Resource, p: f64) -> Self {
     let even = 1.0 / Resource::variant_count() as f64;
     let subject = even + (1.0 - even) * p;
     let rest = (1.0 - subject) / (Resource::variant_count() - 1) as f64;
@@ -119,11 +430,15 @@ impl ResourceProbability {
     Self(inner)
   }
 
-  pub(crate) fn get(&self, resource: Resource) -> f64 {
+  pub fn into_inner(self) -> [f64; Resource::variant_count()] {
+    self.0
+  }
+
+  pub fn get(&self, resource: Resource) -> f64 {
     self.0[resource.ordinal() as usize]
   }
 
-  pub(crate) fn plurality(&self) -> Resource {
+  pub fn plurality(&self) -> Resource {
     Resource::from_ordinal(
       self
         .0
@@ -136,14 +451,95 @@ impl ResourceProbability {
     .unwrap()
   }
 
-  pub(crate) fn normalize(&mut self) {
+  pub fn entropy(&self) -> f64 {
+    -self
+      .0
+      .iter()
+      .filter(|p| **p > 0.0)
+      .map(|p| p * p.log2())
+      .sum::<f64>()
+  }
+
+  /// Updates the belief by Bayes' rule after sensing `sensed` with a sensor
+  /// that's right with probability `accuracy` and otherwise senses any of the
+  /// other resources evenly.
+  pub fn observe(&mut self, sensed: Resource, accuracy: f64) {
+    let miss = (1.0 - accuracy) / (Resource::variant_count() - 1) as f64;
+    let likelihood = |i: usize| {
+      if i == sensed.ordinal() as usize {
+        accuracy
+      } else {
+        miss
+      }
+    };
+    for (i, v) in self.0.iter_mut().enumerate() {
+      *v *= likelihood(i);
+    }
+    // A belief ruling out what was sensed is replaced by the likelihood.
+    if self.0.iter().sum::<f64>() == 0.0 {
+      for (i, v) in self.0.iter_mut().enumerate() {
+        *v = likelihood(i);
+      }
+    }
+    self.normalize();
+  }
+
+  /// Distorts the belief the way a liar would before sharing it.
+  pub fn lie(&mut self, lie: Lie) {
+    match lie {
+      Lie::HideFood => self.shift(Resource::Food, Resource::None),
+      Lie::SwapFoodWater => self.swap(Resource::Food, Resource::Water),
+      Lie::InventGhosts => self.shift(Resource::None, Resource::Ghost),
+    }
+  }
+
+  /// Adds gaussian noise with standard deviation `sigma` to every
+  /// probability.
+  pub fn perturb<R: Rng>(&mut self, rng: &mut R, sigma: f64) {
+    for v in self.0.iter_mut() {
+      // Box-Muller transform.
+      let (u1, u2): (f64, f64) = (1.0 - rng.gen::<f64>(), rng.gen());
+      let normal = (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos();
+      *v = (*v + sigma * normal).max(0.0);
+    }
+    if self.0.iter().sum::<f64>() > 0.0 {
+      self.normalize();
+    } else {
+      self.0 =
+        [1.0 / Resource::variant_count() as f64; Resource::variant_count()];
+    }
+  }
+
+  /// Swaps the most likely resource with another one picked at random.
+  pub fn substitute<R: Rng>(&mut self, rng: &mut R) {
+    let plurality = self.plurality();
+    let other = *Resource::variants()
+      .iter()
+      .filter(|r| **r != plurality)
+      .choose(rng)
+      .unwrap();
+    self.swap(plurality, other);
+  }
+
+  /// Moves all belief in `from` onto `to`.
+  pub fn shift(&mut self, from: Resource, to: Resource) {
+    let from = from.ordinal() as usize;
+    self.0[to.ordinal() as usize] += self.0[from];
+    self.0[from] = 0.0;
+  }
+
+  pub fn swap(&mut self, a: Resource, b: Resource) {
+    self.0.swap(a.ordinal() as usize, b.ordinal() as usize);
+  }
+
+  pub fn normalize(&mut self) {
     let total: f64 = self.0.iter().sum();
     for v in self.0.iter_mut() {
       *v /= total;
     }
   }
 
-  pub(crate) fn resdistribute(&mut self, percent: f64) {
+  pub fn resdistribute(&mut self, percent: f64) {
     let len = self.0.len() as f64;
     for v in self.0.iter_mut() {
       *v -= *v * percent;
@@ -151,7 +547,15 @@ impl ResourceProbability {
     }
   }
 
-  pub(crate) fn adjust_towards(&mut self, other: &Self, trust: f64) {
+  /// Moves `percent` of the way towards `target`.
+  pub fn fade_towards(&mut self, target: &Self, percent: f64) {
+    for (v, t) in self.0.iter_mut().zip(target.0.iter()) {
+      *v -= *v * percent;
+      *v += percent * t;
+    }
+  }
+
+  pub fn adjust_towards(&mut self, other: &Self, trust: f64) {
     for (v, o) in self.0.iter_mut().zip(other.0.iter()) {
       let bias = (0.5 - *v).abs() / 0.5;
       let other_bias = (0.5 - *o).abs() / 0.5;
@@ -0,0 +1,144 @@
+use image::Rgb;
+use serde::{Deserialize, Serialize};
+
+/// Where a belief came from, as the shares of it that were perceived
+/// first-hand, heard from others, or are left over from the initial guess.
+/// Shares are tracked using the weight each update was given.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Provenance {
+  pub first_hand: f32,
+  pub hearsay: f32,
+  /// Mean number of people the hearsay passed through, 1 if it was heard
+  /// straight from whoever saw it.
+  pub hops: f32,
+}
+
+/// A `Provenance` in fixed point, the way brains keep it: shares in 65535ths
+/// and hops in 256ths. Fading a share by less than a 65535th is lost.
+#[derive(
+  Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct PackedProvenance {
+  first_hand: u16,
+  hearsay: u16,
+  hops: u16,
+}
+
+impl PackedProvenance {
+  pub const GUESS: Self = Self {
+    first_hand: 0,
+    hearsay: 0,
+    hops: 0,
+  };
+
+  pub const FIRST_HAND: Self = Self {
+    first_hand: u16::MAX,
+    hearsay: 0,
+    hops: 0,
+  };
+
+  pub fn pack(provenance: &Provenance) -> Self {
+    let share = |v: f32| (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16;
+    Self {
+      first_hand: share(provenance.first_hand),
+      hearsay: share(provenance.hearsay),
+      hops: (provenance.hops * 256.0).round().min(u16::MAX as f32) as u16,
+    }
+  }
+
+  pub fn unpack(&self) -> Provenance {
+    Provenance {
+      first_hand: self.first_hand as f32 / u16::MAX as f32,
+      hearsay: self.hearsay as f32 / u16::MAX as f32,
+      hops: self.hops as f32 / 256.0,
+    }
+  }
+
+  pub fn update<F: FnOnce(&mut Provenance)>(&mut self, f: F) {
+    let mut provenance = self.unpack();
+    f(&mut provenance);
+    *self = Self::pack(&provenance);
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Source {
+  Guess,
+  FirstHand,
+  Hearsay,
+}
+
+impl Provenance {
+  pub const GUESS: Self = Self {
+    first_hand: 0.0,
+    hearsay: 0.0,
+    hops: 0.0,
+  };
+
+  pub const FIRST_HAND: Self = Self {
+    first_hand: 1.0,
+    hearsay: 0.0,
+    hops: 0.0,
+  };
+
+  pub fn guess(&self) -> f32 {
+    (1.0 - self.first_hand - self.hearsay).max(0.0)
+  }
+
+  /// Where most of the belief came from.
+  pub fn source(&self) -> Source {
+    let guess = self.guess();
+    if self.first_hand >= self.hearsay && self.first_hand >= guess {
+      Source::FirstHand
+    } else if self.hearsay >= guess {
+      Source::Hearsay
+    } else {
+      Source::Guess
+    }
+  }
+
+  pub fn perceived(&mut self, weight: f64) {
+    let weight = weight as f32;
+    self.first_hand = self.first_hand * (1.0 - weight) + weight;
+    self.hearsay *= 1.0 - weight;
+  }
+
+  /// Merges in what was heard from someone whose own belief had the
+  /// `speaker` provenance. Everything they knew becomes hearsay, one hop
+  /// further away.
+  pub fn heard(&mut self, speaker: &Provenance, weight: f64) {
+    let weight = weight as f32;
+    let kept = self.hearsay * (1.0 - weight);
+    let relayed = weight * (speaker.first_hand + speaker.hearsay);
+    let relayed_hops = if speaker.first_hand + speaker.hearsay > 0.0 {
+      (speaker.first_hand + speaker.hearsay * (speaker.hops + 1.0))
+        / (speaker.first_hand + speaker.hearsay)
+    } else {
+      0.0
+    };
+
+    self.first_hand *= 1.0 - weight;
+    self.hearsay = kept + relayed;
+    self.hops = if self.hearsay > 0.0 {
+      (kept * self.hops + relayed * relayed_hops) / self.hearsay
+    } else {
+      0.0
+    };
+  }
+
+  /// Shrinks every known share as memory decays back into a guess.
+  pub fn fade(&mut self, percent: f64) {
+    let kept = 1.0 - percent as f32;
+    self.first_hand *= kept;
+    self.hearsay *= kept;
+  }
+
+  /// Green for first-hand, blue for hearsay and black for guesses.
+  pub fn color(&self) -> Rgb<u8> {
+    Rgb([
+      0,
+      (self.first_hand.clamp(0.0, 1.0) * 255.0) as u8,
+      (self.hearsay.clamp(0.0, 1.0) * 255.0) as u8,
+    ])
+  }
+}
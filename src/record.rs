@@ -0,0 +1,299 @@
+use std::{
+  fmt,
+  fs::{self, File, OpenOptions},
+  io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+  path::{Path, PathBuf},
+};
+
+use image::{
+  codecs::gif::{GifEncoder, Repeat},
+  Delay, DynamicImage, Frame, ImageError, RgbImage,
+};
+
+use crate::graphics::Panel;
+use crate::state::State;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordFormat {
+  /// One numbered `.png` per frame in a directory.
+  PngSequence,
+  Gif,
+  Apng,
+}
+
+impl RecordFormat {
+  /// `.gif` and `.png` or `.apng` files are animations, anything else is
+  /// taken as a directory for a PNG sequence.
+  pub fn from_path(path: &Path) -> Self {
+    let extension = path
+      .extension()
+      .and_then(|e| e.to_str())
+      .map(|e| e.to_ascii_lowercase());
+
+    match extension.as_deref() {
+      Some("gif") => RecordFormat::Gif,
+      Some("png") | Some("apng") => RecordFormat::Apng,
+      _ => RecordFormat::PngSequence,
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordOptions {
+  /// Record a frame on every tick divisible by this.
+  pub every: u64,
+  pub panels: Vec<Panel>,
+  /// Time each frame is shown for in animations, in milliseconds.
+  pub delay: u16,
+}
+
+impl Default for RecordOptions {
+  fn default() -> Self {
+    Self {
+      every: 1,
+      panels: Panel::ALL.to_vec(),
+      delay: 100,
+    }
+  }
+}
+
+pub trait FrameWriter {
+  fn write(&mut self, frame: RgbImage, tick: u64) -> Result<(), RecordError>;
+
+  fn finish(&mut self) -> Result<(), RecordError>;
+}
+
+pub struct Recorder {
+  writer: Box<dyn FrameWriter>,
+  options: RecordOptions,
+}
+
+impl Recorder {
+  pub fn create(
+    path: &Path,
+    options: RecordOptions,
+  ) -> Result<Self, RecordError> {
+    if options.every == 0 {
+      return Err(RecordError::Invalid(
+        "frames must be recorded at least every tick".to_owned(),
+      ));
+    }
+    if options.panels.is_empty() {
+      return Err(RecordError::Invalid(
+        "at least one panel must be recorded".to_owned(),
+      ));
+    }
+
+    let writer: Box<dyn FrameWriter> = match RecordFormat::from_path(path) {
+      RecordFormat::PngSequence => Box::new(PngSequenceWriter::new(path)?),
+      RecordFormat::Gif => {
+        Box::new(GifWriter::new(File::create(path)?, options.delay)?)
+      }
+      RecordFormat::Apng => Box::new(ApngWriter::new(path, options.delay)),
+    };
+
+    Ok(Self { writer, options })
+  }
+
+  /// Records the current frame if the tick is one of the recorded ones.
+  pub fn record(&mut self, state: &State) -> Result<(), RecordError> {
+    if !state.tick().is_multiple_of(self.options.every) {
+      return Ok(());
+    }
+    self
+      .writer
+      .write(state.draw_panels(&self.options.panels), state.tick())
+  }
+
+  pub fn finish(&mut self) -> Result<(), RecordError> {
+    self.writer.finish()
+  }
+}
+
+pub struct PngSequenceWriter {
+  dir: PathBuf,
+}
+
+impl PngSequenceWriter {
+  fn new(dir: &Path) -> Result<Self, RecordError> {
+    fs::create_dir_all(dir)?;
+    Ok(Self {
+      dir: dir.to_owned(),
+    })
+  }
+}
+
+impl FrameWriter for PngSequenceWriter {
+  fn write(&mut self, frame: RgbImage, tick: u64) -> Result<(), RecordError> {
+    frame.save(self.dir.join(format!("frame-{:06}.png", tick)))?;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<(), RecordError> {
+    Ok(())
+  }
+}
+
+pub struct GifWriter<W: Write> {
+  encoder: GifEncoder<BufWriter<W>>,
+  delay: Delay,
+}
+
+impl<W: Write> GifWriter<W> {
+  fn new(writer: W, delay: u16) -> Result<Self, RecordError> {
+    let mut encoder = GifEncoder::new_with_speed(BufWriter::new(writer), 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+    Ok(Self {
+      encoder,
+      delay: Delay::from_numer_denom_ms(delay as u32, 1),
+    })
+  }
+}
+
+impl<W: Write> FrameWriter for GifWriter<W> {
+  fn write(&mut self, frame: RgbImage, _tick: u64) -> Result<(), RecordError> {
+    let frame = DynamicImage::ImageRgb8(frame).into_rgba8();
+    self
+      .encoder
+      .encode_frame(Frame::from_parts(frame, 0, 0, self.delay))?;
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<(), RecordError> {
+    Ok(())
+  }
+}
+
+/// Streams frames into an animated PNG. The number of frames has to be known
+/// up front, so a placeholder is written and patched once finished.
+pub struct ApngWriter {
+  path: PathBuf,
+  delay: u16,
+  writer: Option<png::Writer<BufWriter<File>>>,
+  frames: u32,
+}
+
+impl ApngWriter {
+  fn new(path: &Path, delay: u16) -> Self {
+    Self {
+      path: path.to_owned(),
+      delay,
+      writer: None,
+      frames: 0,
+    }
+  }
+
+  fn start(
+    &self,
+    frame: &RgbImage,
+  ) -> Result<png::Writer<BufWriter<File>>, RecordError> {
+    let file = BufWriter::new(File::create(&self.path)?);
+    let mut encoder = png::Encoder::new(file, frame.width(), frame.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(i32::MAX as u32, 0)?;
+    encoder.set_frame_delay(self.delay, 1000)?;
+    Ok(encoder.write_header()?)
+  }
+}
+
+impl FrameWriter for ApngWriter {
+  fn write(&mut self, frame: RgbImage, _tick: u64) -> Result<(), RecordError> {
+    if self.writer.is_none() {
+      self.writer = Some(self.start(&frame)?);
+    }
+    if let Some(writer) = &mut self.writer {
+      writer.write_image_data(frame.as_raw())?;
+      self.frames += 1;
+    }
+    Ok(())
+  }
+
+  fn finish(&mut self) -> Result<(), RecordError> {
+    if let Some(writer) = self.writer.take() {
+      writer.finish()?;
+      set_frame_count(&self.path, self.frames)?;
+    }
+    Ok(())
+  }
+}
+
+/// Rewrites the frame count in the `acTL` chunk of an animated PNG.
+fn set_frame_count(path: &Path, frames: u32) -> Result<(), RecordError> {
+  let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+  let mut offset = 8;
+  loop {
+    let mut header = [0; 8];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut header)?;
+    let length =
+      u32::from_be_bytes([header[0], header[1], header[2], header[3]]);
+
+    if &header[4..] == b"acTL" {
+      let mut chunk = [0; 12];
+      chunk[..4].copy_from_slice(b"acTL");
+      file.read_exact(&mut chunk[4..])?;
+      chunk[4..8].copy_from_slice(&frames.to_be_bytes());
+
+      file.seek(SeekFrom::Start(offset + 8))?;
+      file.write_all(&chunk[4..])?;
+      let mut crc = crc32fast::Hasher::new();
+      crc.update(&chunk);
+      file.write_all(&crc.finalize().to_be_bytes())?;
+      return Ok(());
+    }
+
+    offset += 12 + length as u64;
+  }
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+  Io(io::Error),
+  Image(ImageError),
+  Png(png::EncodingError),
+  Invalid(String),
+}
+
+impl fmt::Display for RecordError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RecordError::Io(e) => write!(f, "failed to write recording: {}", e),
+      RecordError::Image(e) => write!(f, "failed to encode frame: {}", e),
+      RecordError::Png(e) => write!(f, "failed to encode APNG frame: {}", e),
+      RecordError::Invalid(message) => {
+        write!(f, "invalid recording: {}", message)
+      }
+    }
+  }
+}
+
+impl std::error::Error for RecordError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      RecordError::Io(e) => Some(e),
+      RecordError::Image(e) => Some(e),
+      RecordError::Png(e) => Some(e),
+      RecordError::Invalid(_) => None,
+    }
+  }
+}
+
+impl From<io::Error> for RecordError {
+  fn from(e: io::Error) -> Self {
+    RecordError::Io(e)
+  }
+}
+
+impl From<ImageError> for RecordError {
+  fn from(e: ImageError) -> Self {
+    RecordError::Image(e)
+  }
+}
+
+impl From<png::EncodingError> for RecordError {
+  fn from(e: png::EncodingError) -> Self {
+    RecordError::Png(e)
+  }
+}
@@ -1,9 +1,12 @@
 use enum_ordinalize::Ordinalize;
+use serde::{Deserialize, Serialize};
 
 use crate::graphics::Color;
 
-#[derive(Clone, Copy, PartialEq, Eq, Ordinalize)]
-pub(crate) enum Resource {
+#[derive(
+  Clone, Copy, Debug, PartialEq, Eq, Ordinalize, Serialize, Deserialize,
+)]
+pub enum Resource {
   None,
   Food,
   Water,
@@ -12,7 +15,7 @@ pub(crate) enum Resource {
 }
 
 impl Resource {
-  pub(crate) fn color(&self) -> Color {
+  pub fn color(&self) -> Color {
     match self {
       Resource::None => [0.0, 0.0, 0.0],
       Resource::Food => [0.0, 1.0, 0.0],
@@ -0,0 +1,188 @@
+use std::{
+  fmt,
+  fs::File,
+  io::{self, BufReader, BufWriter, Read, Write},
+  path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::ConfigError;
+use crate::state::State;
+
+const MAGIC: &[u8; 8] = b"INFODIST";
+
+/// Bumped whenever the layout of `State` changes in a way older snapshots
+/// can't be read with.
+pub const SNAPSHOT_VERSION: u32 = 21;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotFormat {
+  Binary,
+  Json,
+}
+
+impl SnapshotFormat {
+  pub fn from_path(path: &Path) -> Self {
+    match path.extension() {
+      Some(ext) if ext.eq_ignore_ascii_case("json") => SnapshotFormat::Json,
+      _ => SnapshotFormat::Binary,
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      SnapshotFormat::Binary => "bin",
+      SnapshotFormat::Json => "json",
+    }
+  }
+}
+
+#[derive(Serialize)]
+struct SnapshotRef<'a> {
+  version: u32,
+  state: &'a State,
+}
+
+#[derive(Deserialize)]
+struct SnapshotVersion {
+  version: u32,
+}
+
+#[derive(Deserialize)]
+struct Snapshot {
+  state: State,
+}
+
+pub fn save(state: &State, path: &Path) -> Result<(), SnapshotError> {
+  let mut writer = BufWriter::new(File::create(path)?);
+
+  match SnapshotFormat::from_path(path) {
+    SnapshotFormat::Binary => {
+      writer.write_all(MAGIC)?;
+      writer.write_all(&SNAPSHOT_VERSION.to_le_bytes())?;
+      bincode::serialize_into(&mut writer, state)?;
+    }
+    SnapshotFormat::Json => {
+      serde_json::to_writer(
+        &mut writer,
+        &SnapshotRef {
+          version: SNAPSHOT_VERSION,
+          state,
+        },
+      )?;
+    }
+  }
+
+  writer.flush()?;
+  Ok(())
+}
+
+pub fn load(path: &Path) -> Result<State, SnapshotError> {
+  let mut reader = BufReader::new(File::open(path)?);
+
+  let mut state: State = match SnapshotFormat::from_path(path) {
+    SnapshotFormat::Binary => {
+      let mut magic = [0; 8];
+      reader.read_exact(&mut magic)?;
+      if &magic != MAGIC {
+        return Err(SnapshotError::NotASnapshot);
+      }
+
+      let mut version = [0; 4];
+      reader.read_exact(&mut version)?;
+      check_version(u32::from_le_bytes(version))?;
+
+      bincode::deserialize_from(reader)?
+    }
+    SnapshotFormat::Json => {
+      let value: serde_json::Value = serde_json::from_reader(reader)?;
+      let SnapshotVersion { version } = serde_json::from_value(value.clone())?;
+      check_version(version)?;
+
+      let Snapshot { state } = serde_json::from_value(value)?;
+      state
+    }
+  };
+
+  state.config.validate()?;
+  state.restore_pipeline()?;
+  Ok(state)
+}
+
+fn check_version(version: u32) -> Result<(), SnapshotError> {
+  if version == SNAPSHOT_VERSION {
+    Ok(())
+  } else {
+    Err(SnapshotError::UnsupportedVersion(version))
+  }
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+  Io(io::Error),
+  Binary(bincode::Error),
+  Json(serde_json::Error),
+  Config(ConfigError),
+  NotASnapshot,
+  UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SnapshotError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      SnapshotError::Io(e) => write!(f, "failed to access snapshot: {}", e),
+      SnapshotError::Binary(e) => {
+        write!(f, "failed to encode binary snapshot: {}", e)
+      }
+      SnapshotError::Json(e) => {
+        write!(f, "failed to encode JSON snapshot: {}", e)
+      }
+      SnapshotError::Config(e) => write!(f, "snapshot has {}", e),
+      SnapshotError::NotASnapshot => write!(f, "file is not a snapshot"),
+      SnapshotError::UnsupportedVersion(v) => write!(
+        f,
+        "snapshot version {} is not supported, expected {}",
+        v, SNAPSHOT_VERSION
+      ),
+    }
+  }
+}
+
+impl std::error::Error for SnapshotError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      SnapshotError::Io(e) => Some(e),
+      SnapshotError::Binary(e) => Some(e),
+      SnapshotError::Json(e) => Some(e),
+      SnapshotError::Config(e) => Some(e),
+      SnapshotError::NotASnapshot | SnapshotError::UnsupportedVersion(_) => {
+        None
+      }
+    }
+  }
+}
+
+impl From<io::Error> for SnapshotError {
+  fn from(e: io::Error) -> Self {
+    SnapshotError::Io(e)
+  }
+}
+
+impl From<bincode::Error> for SnapshotError {
+  fn from(e: bincode::Error) -> Self {
+    SnapshotError::Binary(e)
+  }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+  fn from(e: serde_json::Error) -> Self {
+    SnapshotError::Json(e)
+  }
+}
+
+impl From<ConfigError> for SnapshotError {
+  fn from(e: ConfigError) -> Self {
+    SnapshotError::Config(e)
+  }
+}
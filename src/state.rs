@@ -0,0 +1,355 @@
+use image::{GenericImage, Rgb, RgbImage, SubImage};
+use ndarray::{Array2, Array3, Axis, Zip};
+use rand::{prelude::*, seq::index};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::channel::Channel;
+use crate::config::{ConfigError, SimConfig};
+use crate::graphics::{ImageGrid, Panel};
+use crate::map::Map;
+use crate::message::Traffic;
+use crate::network::Network;
+use crate::person::{Brain, Needs, Person, Role};
+use crate::resource::Resource;
+use crate::systems::Pipeline;
+use crate::trust::Trust;
+
+pub type SimRng = ChaCha8Rng;
+
+#[derive(Serialize, Deserialize)]
+pub struct State {
+  pub(crate) config: SimConfig,
+  pub(crate) seed: u64,
+  pub(crate) rng: SimRng,
+  pub(crate) map: Map,
+  pub(crate) people: Vec<Person>,
+  pub(crate) network: Network,
+  pub(crate) channel: Channel,
+  pub(crate) tick: u64,
+  /// What was said this tick.
+  pub(crate) traffic: Traffic,
+  pub(crate) total_traffic: Traffic,
+  #[serde(skip)]
+  selected_person: usize,
+  #[serde(skip)]
+  pipeline: Pipeline,
+}
+
+pub struct World<'a> {
+  pub config: &'a SimConfig,
+  pub tick: u64,
+  pub rng: &'a mut SimRng,
+  pub map: &'a mut Map,
+  pub people: &'a mut Vec<Person>,
+  pub network: &'a Network,
+}
+
+impl State {
+  pub fn gen(config: SimConfig, seed: u64) -> Result<Self, ConfigError> {
+    config.validate()?;
+    let pipeline = Pipeline::from_names(&config.pipeline.systems)?;
+    let mut rng = SimRng::seed_from_u64(seed);
+    let map = Map::gen(&mut rng, &config.map);
+
+    let mut models = config.people.beliefs.models(config.people.count);
+    if models.iter().any(|m| *m != models[0]) {
+      models.shuffle(&mut rng);
+    }
+
+    let layout = config.memory.layout();
+    let learning = config.communication.trust_learning_rate > 0.0;
+    let mut people: Vec<_> = models
+      .into_iter()
+      .enumerate()
+      .map(|(id, model)| {
+        let brain = Brain::gen(
+          &mut rng,
+          map.width(),
+          map.height(),
+          model,
+          &config.memory,
+        );
+        let x = rng.gen_range(0..map.width());
+        let y = rng.gen_range(0..map.height());
+        Person {
+          id,
+          role: Role::Honest,
+          brain,
+          x,
+          y,
+          home: (x, y),
+          needs: Needs {
+            hunger: 0,
+            thirst: 0,
+          },
+          trust: Trust::new(map.width(), map.height(), layout, learning),
+          talking: false,
+        }
+      })
+      .collect();
+
+    let network = Network::gen(&mut rng, &config.network, config.people.count)?;
+
+    let liars = config.people.liar_count();
+    if liars > 0 {
+      for i in index::sample(&mut rng, people.len(), liars) {
+        people[i].role = Role::Liar;
+      }
+    }
+
+    Ok(Self {
+      config,
+      seed,
+      rng,
+      map,
+      people,
+      network,
+      channel: Channel::default(),
+      tick: 0,
+      traffic: Traffic::default(),
+      total_traffic: Traffic::default(),
+      selected_person: 0,
+      pipeline,
+    })
+  }
+
+  pub fn seed(&self) -> u64 {
+    self.seed
+  }
+
+  pub fn tick(&self) -> u64 {
+    self.tick
+  }
+
+  pub fn config(&self) -> &SimConfig {
+    &self.config
+  }
+
+  pub fn map(&self) -> &Map {
+    &self.map
+  }
+
+  pub fn people(&self) -> &[Person] {
+    &self.people
+  }
+
+  /// What was said in the last tick.
+  pub fn traffic(&self) -> Traffic {
+    self.traffic
+  }
+
+  /// What was said over the whole run.
+  pub fn total_traffic(&self) -> Traffic {
+    self.total_traffic
+  }
+
+  pub fn network(&self) -> &Network {
+    &self.network
+  }
+
+  pub fn channel(&self) -> &Channel {
+    &self.channel
+  }
+
+  pub fn person(&self, id: usize) -> Option<&Person> {
+    self.people.iter().find(|p| p.id == id)
+  }
+
+  pub fn selected_person(&self) -> Option<&Person> {
+    self.people.get(self.selected_person)
+  }
+
+  /// Selects the person before the selected one, wrapping around. Does
+  /// nothing once everyone has died.
+  pub fn select_previous_person(&mut self) {
+    if self.people.is_empty() {
+      return;
+    }
+    // Whoever was selected may have died since, leaving the index past the
+    // end.
+    self.selected_person = self
+      .selected_person
+      .min(self.people.len())
+      .checked_sub(1)
+      .unwrap_or(self.people.len() - 1);
+  }
+
+  /// Selects the person after the selected one, wrapping around. Does
+  /// nothing once everyone has died.
+  pub fn select_next_person(&mut self) {
+    if self.people.is_empty() {
+      return;
+    }
+    self.selected_person = (self.selected_person + 1) % self.people.len();
+  }
+
+  pub fn update(&mut self) {
+    self.tick += 1;
+    self.traffic = Traffic::default();
+
+    let mut pipeline = std::mem::take(&mut self.pipeline);
+    pipeline.run(self);
+    self.pipeline = pipeline;
+
+    self.total_traffic += self.traffic;
+  }
+
+  /// Borrows the parts of the state a system works on separately, so they can
+  /// be changed together from outside the crate.
+  pub fn world_mut(&mut self) -> World<'_> {
+    World {
+      config: &self.config,
+      tick: self.tick,
+      rng: &mut self.rng,
+      map: &mut self.map,
+      people: &mut self.people,
+      network: &self.network,
+    }
+  }
+
+  pub fn pipeline(&self) -> &Pipeline {
+    &self.pipeline
+  }
+
+  pub fn pipeline_mut(&mut self) -> &mut Pipeline {
+    &mut self.pipeline
+  }
+
+  /// Rebuilds the pipeline from the config, as it isn't part of snapshots.
+  pub(crate) fn restore_pipeline(&mut self) -> Result<(), ConfigError> {
+    self.pipeline = Pipeline::from_names(&self.config.pipeline.systems)?;
+    Ok(())
+  }
+
+  pub fn collective_view(&self) -> Array2<Resource> {
+    let mut votes = Array3::from_elem(
+      (
+        self.map.width(),
+        self.map.height(),
+        Resource::variant_count(),
+      ),
+      0,
+    );
+
+    for person in self.people.iter() {
+      person.brain.beliefs.for_each(|(x, y), v| {
+        *votes
+          .get_mut((x, y, v.most_likely().ordinal() as usize))
+          .unwrap() += 1;
+      });
+    }
+
+    votes.map_axis(Axis(2), |votes| {
+      Resource::from_ordinal(
+        votes.indexed_iter().max_by_key(|(_, v)| **v).unwrap().0 as i8,
+      )
+      .unwrap()
+    })
+  }
+
+  pub fn draw(&self) -> RgbImage {
+    self.draw_panels(&Panel::ALL)
+  }
+
+  /// Draws only the given panels, in order, three to a row.
+  pub fn draw_panels(&self, panels: &[Panel]) -> RgbImage {
+    let columns = panels.len().clamp(1, 3);
+    let rows = panels.len().div_ceil(columns).max(1);
+    let mut img =
+      ImageGrid::new(self.map.width(), self.map.height(), columns, rows);
+
+    for (i, panel) in panels.iter().enumerate() {
+      self.draw_panel(*panel, &mut img.grid_mut(i % columns, i / columns));
+    }
+
+    img.into_inner()
+  }
+
+  fn draw_panel(&self, panel: Panel, buffer: &mut SubImage<&mut RgbImage>) {
+    match panel {
+      Panel::Map => self.map.draw(buffer),
+      Panel::People => {
+        for (i, person) in self.people.iter().enumerate() {
+          buffer.put_pixel(
+            person.x as u32,
+            person.y as u32,
+            if self.selected_person == i {
+              Rgb([255, 255, 255])
+            } else if person.is_liar() {
+              Rgb([255, 0, 255])
+            } else {
+              Rgb([255, 0, 0])
+            },
+          );
+        }
+      }
+      Panel::CollectiveError => {
+        let error = error_map(&self.collective_view(), &self.map.resources);
+        draw_error(&error, buffer);
+      }
+      Panel::Brain
+      | Panel::Provenance
+      | Panel::Certainty
+      | Panel::Favorability
+      | Panel::Error => {
+        if let Some(selected_person) = self.selected_person() {
+          self.draw_selected_person(selected_person, panel, buffer);
+        }
+      }
+    }
+  }
+
+  fn draw_selected_person(
+    &self,
+    selected_person: &Person,
+    panel: Panel,
+    buffer: &mut SubImage<&mut RgbImage>,
+  ) {
+    match panel {
+      Panel::Brain => selected_person.brain.draw(buffer),
+      Panel::Provenance => selected_person.brain.draw_provenance(buffer),
+      Panel::Certainty => selected_person.brain.draw_confidence(buffer),
+      Panel::Favorability => {
+        let favorability = selected_person.favorability_map(&self.config.needs);
+        let min = favorability.fold(0.0f64, |acc, cur| acc.min(*cur));
+        let max = favorability.fold(0.0f64, |acc, cur| acc.max(*cur));
+
+        for ((x, y), v) in favorability.indexed_iter() {
+          let as_u8 = (((*v - min) / (max - min)) * 255.0) as u8;
+          buffer.put_pixel(x as u32, y as u32, Rgb([as_u8, as_u8, as_u8]));
+        }
+      }
+      Panel::Error => {
+        let error = error_map(
+          &selected_person.brain.plurality_map(),
+          &self.map.resources,
+        );
+        draw_error(&error, buffer);
+      }
+      _ => {}
+    }
+  }
+}
+
+fn draw_error(error: &Array2<bool>, buffer: &mut SubImage<&mut RgbImage>) {
+  for ((x, y), v) in error.indexed_iter() {
+    let as_u8 = if *v { 255 } else { 0 };
+    buffer.put_pixel(x as u32, y as u32, Rgb([as_u8, as_u8, as_u8]));
+  }
+}
+
+pub fn error_map(
+  view: &Array2<Resource>,
+  resources: &Array2<Resource>,
+) -> Array2<bool> {
+  Zip::from(view).and(resources).map_collect(|b, m| b != m)
+}
+
+pub fn error_rate(
+  view: &Array2<Resource>,
+  resources: &Array2<Resource>,
+) -> f64 {
+  let errors = error_map(view, resources).iter().filter(|e| **e).count();
+  errors as f64 / resources.len() as f64
+}
@@ -0,0 +1,247 @@
+use std::{
+  fs,
+  io::Write,
+  path::Path,
+  sync::atomic::{AtomicUsize, Ordering},
+};
+
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use crate::config::{ConfigError, SimConfig};
+use crate::export::{self, ExportError};
+use crate::metrics::{mean, TickMetrics};
+use crate::state::State;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SweepSpec {
+  /// Number of seeds to run for every combination of parameters.
+  pub seeds: u64,
+  #[serde(default)]
+  pub first_seed: u64,
+  pub ticks: u64,
+  #[serde(default)]
+  pub parameters: Vec<Parameter>,
+}
+
+impl SweepSpec {
+  pub fn load(path: &Path) -> Result<Self, ConfigError> {
+    let spec: Self = toml::from_str(&fs::read_to_string(path)?)?;
+    if spec.seeds == 0 {
+      return Err(ConfigError::Invalid(
+        "sweep needs at least one seed".to_owned(),
+      ));
+    }
+    Ok(spec)
+  }
+
+  pub fn combinations(&self) -> Vec<Vec<toml::Value>> {
+    self
+      .parameters
+      .iter()
+      .fold(vec![vec![]], |combinations, parameter| {
+        combinations
+          .iter()
+          .flat_map(|c| {
+            parameter.values().into_iter().map(move |v| {
+              let mut c = c.clone();
+              c.push(v);
+              c
+            })
+          })
+          .collect()
+      })
+  }
+}
+
+#[derive(Deserialize)]
+pub struct Parameter {
+  /// Dotted path into the config, such as `perception.sense_range`.
+  pub name: String,
+  #[serde(flatten)]
+  pub values: ParameterValues,
+}
+
+impl Parameter {
+  pub fn values(&self) -> Vec<toml::Value> {
+    match &self.values {
+      ParameterValues::List { values } => values.clone(),
+      ParameterValues::Range { start, end, step } => match (start, end, step) {
+        (
+          toml::Value::Integer(start),
+          toml::Value::Integer(end),
+          toml::Value::Integer(step),
+        ) if *step > 0 => (*start..=*end)
+          .step_by(*step as usize)
+          .map(toml::Value::Integer)
+          .collect(),
+        _ => {
+          let (start, end, step) = (as_f64(start), as_f64(end), as_f64(step));
+          if step <= 0.0 {
+            return vec![];
+          }
+          (0..)
+            .map(|i| start + i as f64 * step)
+            .take_while(|v| *v <= end + step * 1e-9)
+            .map(toml::Value::Float)
+            .collect()
+        }
+      },
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ParameterValues {
+  List {
+    values: Vec<toml::Value>,
+  },
+  Range {
+    start: toml::Value,
+    end: toml::Value,
+    step: toml::Value,
+  },
+}
+
+fn as_f64(value: &toml::Value) -> f64 {
+  match value {
+    toml::Value::Integer(v) => *v as f64,
+    toml::Value::Float(v) => *v,
+    _ => f64::NAN,
+  }
+}
+
+pub struct SweepRun {
+  pub combination: usize,
+  pub seed: u64,
+  pub survival_time: u64,
+  pub last: TickMetrics,
+}
+
+pub struct SweepResults {
+  pub combinations: Vec<Vec<toml::Value>>,
+  pub runs: Vec<SweepRun>,
+}
+
+/// Runs every combination of parameters once per seed on top of `base`,
+/// spread over the current rayon thread pool. `on_finished` is called with
+/// the number of finished runs and the total after each run.
+pub fn run<F>(
+  base: &SimConfig,
+  spec: &SweepSpec,
+  on_finished: F,
+) -> Result<SweepResults, ConfigError>
+where
+  F: Fn(usize, usize) + Sync,
+{
+  let combinations = spec.combinations();
+  let configs = combinations
+    .iter()
+    .map(|values| {
+      spec.parameters.iter().zip(values.iter()).try_fold(
+        base.clone(),
+        |config, (parameter, value)| {
+          config.with_parameter(&parameter.name, value.clone())
+        },
+      )
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+  let runs: Vec<_> = (0..configs.len())
+    .flat_map(|c| (0..spec.seeds).map(move |s| (c, spec.first_seed + s)))
+    .collect();
+
+  let finished = AtomicUsize::new(0);
+  let runs = runs
+    .par_iter()
+    .map(|(combination, seed)| {
+      let mut state = State::gen(configs[*combination].clone(), *seed)?;
+      while state.tick() < spec.ticks && !state.people().is_empty() {
+        state.update();
+      }
+
+      on_finished(finished.fetch_add(1, Ordering::Relaxed) + 1, runs.len());
+
+      Ok(SweepRun {
+        combination: *combination,
+        seed: *seed,
+        survival_time: state.tick(),
+        last: TickMetrics::collect(&state),
+      })
+    })
+    .collect::<Result<_, ConfigError>>()?;
+
+  Ok(SweepResults { combinations, runs })
+}
+
+impl SweepResults {
+  /// Writes one row per combination of parameters, with the mean and
+  /// variance across seeds of every aggregate.
+  pub fn write_csv<W: Write>(
+    &self,
+    mut writer: W,
+    base: &SimConfig,
+    spec: &SweepSpec,
+  ) -> Result<(), ExportError> {
+    writeln!(
+      writer,
+      "# seeds = {}..{}",
+      spec.first_seed,
+      spec.first_seed + spec.seeds
+    )?;
+    writeln!(writer, "# ticks = {}", spec.ticks)?;
+    export::write_comment(&mut writer, base)?;
+
+    let mut writer = csv::Writer::from_writer(writer);
+
+    let mut header: Vec<_> =
+      spec.parameters.iter().map(|p| p.name.clone()).collect();
+    header.push("runs".to_owned());
+    for metric in AGGREGATES.iter().map(|(name, _)| name) {
+      header.push(format!("{}_mean", metric));
+      header.push(format!("{}_variance", metric));
+    }
+    writer.write_record(&header)?;
+
+    for (i, values) in self.combinations.iter().enumerate() {
+      let runs: Vec<_> =
+        self.runs.iter().filter(|r| r.combination == i).collect();
+
+      let mut record: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+      record.push(runs.len().to_string());
+      for (_, metric) in AGGREGATES.iter() {
+        let values: Vec<_> = runs.iter().filter_map(|r| metric(r)).collect();
+        let (mean, variance) = match mean(values.iter().copied()) {
+          Some(m) => (
+            m.to_string(),
+            mean(values.iter().map(|v| (v - m).powi(2)))
+              .unwrap()
+              .to_string(),
+          ),
+          None => (String::new(), String::new()),
+        };
+        record.push(mean);
+        record.push(variance);
+      }
+      writer.write_record(&record)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+  }
+}
+
+type Aggregate = (&'static str, fn(&SweepRun) -> Option<f64>);
+
+/// Final values averaged across seeds. Error and entropy are left out of runs
+/// in which everyone died.
+const AGGREGATES: [Aggregate; 6] = [
+  ("survival_time", |r| Some(r.survival_time as f64)),
+  ("population", |r| Some(r.last.population as f64)),
+  ("collective_error", |r| r.last.collective_error),
+  ("mean_individual_error", |r| r.last.mean_individual_error),
+  ("mean_honest_error", |r| r.last.mean_honest_error),
+  ("mean_entropy", |r| r.last.mean_entropy),
+];
@@ -0,0 +1,313 @@
+use rand::prelude::*;
+use rand::seq::index;
+
+use super::System;
+use crate::config::{CommunicationConfig, Protocol, SimConfig, TalkPolicy};
+use crate::message::{Message, Traffic};
+use crate::person::Person;
+use crate::state::State;
+use crate::trust::Claim;
+
+/// Has people talk to others they're linked to and in range of, either
+/// pairing everyone up at random or having each contact a few others, and
+/// share what they believe following the configured protocol. Conversations
+/// can cost those in them hunger, thirst or their next move, and people can be
+/// set to only talk when they can afford to.
+pub struct Communication;
+
+impl System for Communication {
+  fn name(&self) -> &str {
+    "communication"
+  }
+
+  fn run(&mut self, state: &mut State) {
+    let State {
+      config,
+      people,
+      network,
+      channel,
+      rng,
+      traffic,
+      tick,
+      ..
+    } = state;
+
+    let communication = &config.communication;
+    let range = communication.range;
+    let willing: Vec<_> = people
+      .iter()
+      .map(|p| match communication.policy {
+        TalkPolicy::Always => true,
+        TalkPolicy::WhenSated => {
+          p.needs.below(&config.needs, communication.sated)
+        }
+      })
+      .collect();
+    traffic.declined += willing.iter().filter(|w| !**w).count() as u64;
+
+    let conversations = match (communication.fanout, network.edges()) {
+      (None, None) => {
+        let mut shuffled: Vec<_> = (0..people.len()).collect();
+        shuffled.shuffle(rng);
+        shuffled.retain(|a| willing[*a]);
+        pairs(people, &shuffled, range)
+      }
+      (None, Some(edges)) => {
+        let mut pairs = linked_pairs(rng, people, edges, &willing, range);
+        // Links are stored lowest id first, so pick who starts at random.
+        if communication.protocol != Protocol::PushPull {
+          for (a, b) in pairs.iter_mut() {
+            if rng.gen() {
+              std::mem::swap(a, b);
+            }
+          }
+        }
+        pairs
+      }
+      (Some(fanout), edges) => {
+        contacts(rng, people, edges, &willing, range, fanout)
+      }
+    };
+
+    // Everything is said before anything is heard, so nothing spreads
+    // further than one conversation per tick.
+    for (a, b) in conversations {
+      traffic.conversations += 1;
+      let mut talk = |speaker: usize, listener: usize| {
+        let message = compose(rng, &people[speaker], config, traffic);
+        let to = people[listener].id;
+        channel.send(rng, &config.channel, *tick, to, message, traffic);
+      };
+      if communication.protocol != Protocol::Pull {
+        talk(a, b);
+      }
+      if communication.protocol != Protocol::Push {
+        talk(b, a);
+      }
+
+      let cost = &communication.cost;
+      for person in [a, b] {
+        let person = &mut people[person];
+        person.needs.hunger = person.needs.hunger.saturating_add(cost.hunger);
+        person.needs.thirst = person.needs.thirst.saturating_add(cost.thirst);
+        person.talking |= cost.stay;
+      }
+    }
+
+    let index = index_of(people);
+    for (to, message) in channel.receive(*tick) {
+      // Messages for people who died on the way are lost.
+      if let Some(listener) = index(to) {
+        listen(&mut people[listener], &message, communication, *tick);
+      }
+    }
+  }
+}
+
+fn compose<R: Rng>(
+  rng: &mut R,
+  speaker: &Person,
+  config: &SimConfig,
+  traffic: &mut Traffic,
+) -> Message {
+  let message =
+    Message::compose(rng, speaker, &config.communication, config.people.lie);
+  traffic.cells += message.cells.len() as u64;
+  if speaker.is_liar() {
+    traffic.lies += message.cells.len() as u64;
+  }
+  message
+}
+
+fn listen(
+  listener: &mut Person,
+  message: &Message,
+  config: &CommunicationConfig,
+  tick: u64,
+) {
+  let trust = listener.trust.of(message.from, config.trust);
+  let learning = config.trust_learning_rate > 0.0;
+
+  for shared in message.cells.iter() {
+    let (x, y) = (shared.x, shared.y);
+    let believed = listener.brain.observed[(x, y)].tick();
+
+    let trust = match config.freshness_half_life {
+      Some(half_life) => {
+        let freshness = |observed: Option<u64>| match observed {
+          Some(observed) => {
+            0.5f64.powf(tick.saturating_sub(observed) as f64 / half_life)
+          }
+          None => 0.0,
+        };
+        let (heard, own) = (freshness(shared.observed), freshness(believed));
+        if heard + own > 0.0 {
+          (trust * 2.0 * heard / (heard + own)).min(1.0)
+        } else {
+          trust
+        }
+      }
+      None => trust,
+    };
+
+    // Merging can leave a cell at the default, which isn't worth storing.
+    let brain = &mut listener.brain;
+    brain.beliefs.update((x, y), |b| {
+      b.merge(&shared.belief, shared.concentration, trust)
+    });
+    brain
+      .observed
+      .set((x, y), believed.max(shared.observed).into());
+    brain
+      .provenance
+      .update((x, y), |p| p.update(|p| p.heard(&shared.provenance, trust)));
+    if learning {
+      listener.trust.record_claim(
+        x,
+        y,
+        Claim {
+          from: message.from,
+          resource: shared.belief.plurality(),
+        },
+      );
+    }
+  }
+}
+
+fn in_range(a: &Person, b: &Person, range: Option<f64>) -> bool {
+  match range {
+    Some(range) => a.distance(b) <= range,
+    None => true,
+  }
+}
+
+/// Goes through people in shuffled order, pairing each with the next unpaired
+/// person within range.
+fn pairs(
+  people: &[Person],
+  shuffled: &[usize],
+  range: Option<f64>,
+) -> Vec<(usize, usize)> {
+  let mut paired = vec![false; people.len()];
+  let mut pairs = vec![];
+  for (i, &a) in shuffled.iter().enumerate() {
+    if paired[a] {
+      continue;
+    }
+    let partner = shuffled[i + 1..]
+      .iter()
+      .copied()
+      .find(|&b| !paired[b] && in_range(&people[a], &people[b], range));
+    if let Some(b) = partner {
+      paired[a] = true;
+      paired[b] = true;
+      pairs.push((a, b));
+    }
+  }
+  pairs
+}
+
+/// Goes through the links between living people in shuffled order, pairing
+/// up the ends of each link whose ends are both willing and still unpaired.
+fn linked_pairs<R: Rng>(
+  rng: &mut R,
+  people: &[Person],
+  edges: &[(usize, usize)],
+  willing: &[bool],
+  range: Option<f64>,
+) -> Vec<(usize, usize)> {
+  let index = index_of(people);
+  let mut links: Vec<_> = edges
+    .iter()
+    .filter_map(|(a, b)| Some((index(*a)?, index(*b)?)))
+    .filter(|(a, b)| in_range(&people[*a], &people[*b], range))
+    .collect();
+  links.shuffle(rng);
+  links.retain(|(a, b)| willing[*a] && willing[*b]);
+
+  let mut paired = vec![false; people.len()];
+  let mut pairs = vec![];
+  for (a, b) in links {
+    if !paired[a] && !paired[b] {
+      paired[a] = true;
+      paired[b] = true;
+      pairs.push((a, b));
+    }
+  }
+  pairs
+}
+
+/// Has everyone willing, in shuffled order, contact up to `fanout` willing
+/// people picked at random from those they're linked to and in range of. The
+/// same person can be contacted by several others. Without a network, those
+/// contacted are picked straight from everyone willing rather than listing
+/// every link.
+fn contacts<R: Rng>(
+  rng: &mut R,
+  people: &[Person],
+  edges: Option<&[(usize, usize)]>,
+  willing: &[bool],
+  range: Option<f64>,
+  fanout: usize,
+) -> Vec<(usize, usize)> {
+  let linked = edges.map(|edges| {
+    let index = index_of(people);
+    let mut linked = vec![vec![]; people.len()];
+    for (a, b) in edges {
+      if let (Some(a), Some(b)) = (index(*a), index(*b)) {
+        linked[a].push(b);
+        linked[b].push(a);
+      }
+    }
+    linked
+  });
+  let everyone: Vec<_> = (0..people.len()).filter(|b| willing[*b]).collect();
+
+  let mut shuffled: Vec<_> = (0..people.len()).collect();
+  shuffled.shuffle(rng);
+
+  let mut contacts = vec![];
+  for a in shuffled {
+    if !willing[a] {
+      continue;
+    }
+    let reachable = |b: &usize| {
+      *b != a && willing[*b] && in_range(&people[a], &people[*b], range)
+    };
+    let picked: Vec<_> = match (&linked, range) {
+      (Some(linked), _) => {
+        let candidates: Vec<_> =
+          linked[a].iter().copied().filter(reachable).collect();
+        candidates.choose_multiple(rng, fanout).copied().collect()
+      }
+      (None, Some(_)) => everyone
+        .iter()
+        .copied()
+        .filter(reachable)
+        .choose_multiple(rng, fanout),
+      // Everyone willing is in reach, so pick one more than needed in case
+      // `a` is among them.
+      (None, None) => {
+        let amount = (fanout + 1).min(everyone.len());
+        index::sample(rng, everyone.len(), amount)
+          .into_iter()
+          .map(|i| everyone[i])
+          .filter(|b| *b != a)
+          .take(fanout)
+          .collect()
+      }
+    };
+    contacts.extend(picked.into_iter().map(|b| (a, b)));
+  }
+  contacts
+}
+
+/// Looks up where a person is in `people` by their id.
+fn index_of(people: &[Person]) -> impl Fn(usize) -> Option<usize> {
+  let mut index =
+    vec![None; people.iter().map(|p| p.id + 1).max().unwrap_or(0)];
+  for (i, person) in people.iter().enumerate() {
+    index[person.id] = Some(i);
+  }
+  move |id| index.get(id).copied().flatten()
+}
@@ -0,0 +1,38 @@
+use rand::prelude::*;
+
+use super::System;
+use crate::ndarray_pad::{ArrayPaddingExt, ArrayPaddingKind};
+use crate::resource::Resource;
+use crate::state::State;
+
+pub struct MapMutation;
+
+impl System for MapMutation {
+  fn name(&self) -> &str {
+    "map_mutation"
+  }
+
+  fn run(&mut self, state: &mut State) {
+    let State {
+      config, map, rng, ..
+    } = state;
+
+    let mutation = &config.map.mutation;
+    let paddded = map.resources.pad((1, 1), ArrayPaddingKind::Clamp);
+    let windows: Vec<_> = paddded.windows((3, 3)).into_iter().collect();
+    let resources = &mut map.resources;
+    let biomes = &map.biome_map;
+    for (((x, y), r), w) in resources.indexed_iter_mut().zip(windows) {
+      let roll: f64 = rng.gen();
+      *r = if roll < mutation.neighbor {
+        *w.iter().choose(rng).unwrap()
+      } else if roll < mutation.neighbor + mutation.biome {
+        biomes[(x, y)].gen_resource(rng)
+      } else if roll < mutation.neighbor + mutation.biome + mutation.random {
+        *Resource::variants().choose(rng).unwrap()
+      } else {
+        w[(1, 1)]
+      };
+    }
+  }
+}
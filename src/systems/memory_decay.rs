@@ -0,0 +1,18 @@
+use super::System;
+use crate::state::State;
+
+pub struct MemoryDecay;
+
+impl System for MemoryDecay {
+  fn name(&self) -> &str {
+    "memory_decay"
+  }
+
+  fn run(&mut self, state: &mut State) {
+    let memory = &state.config.memory;
+    for person in state.people.iter_mut() {
+      let home = person.home;
+      person.brain.decay(memory, &state.map, home, state.tick);
+    }
+  }
+}
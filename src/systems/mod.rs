@@ -0,0 +1,105 @@
+mod communication;
+mod map_mutation;
+mod memory_decay;
+mod movement;
+mod needs;
+mod perception;
+
+pub use communication::Communication;
+pub use map_mutation::MapMutation;
+pub use memory_decay::MemoryDecay;
+pub use movement::Movement;
+pub use needs::NeedsIncrease;
+pub use perception::Perception;
+
+use crate::config::ConfigError;
+use crate::state::State;
+
+/// One phase of a tick. Systems run in order on the whole state, each seeing
+/// the changes made by the ones before it.
+pub trait System: Send {
+  fn name(&self) -> &str;
+
+  fn run(&mut self, state: &mut State);
+}
+
+/// Names of the built in systems, in the order they run by default.
+pub const BUILTIN_SYSTEMS: [&str; 6] = [
+  "map_mutation",
+  "needs",
+  "memory_decay",
+  "perception",
+  "movement",
+  "communication",
+];
+
+pub fn builtin(name: &str) -> Option<Box<dyn System>> {
+  Some(match name {
+    "map_mutation" => Box::new(MapMutation),
+    "needs" => Box::new(NeedsIncrease),
+    "memory_decay" => Box::new(MemoryDecay),
+    "perception" => Box::new(Perception),
+    "movement" => Box::new(Movement),
+    "communication" => Box::new(Communication),
+    _ => return None,
+  })
+}
+
+#[derive(Default)]
+pub struct Pipeline {
+  systems: Vec<Box<dyn System>>,
+}
+
+impl Pipeline {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn from_names<S: AsRef<str>>(names: &[S]) -> Result<Self, ConfigError> {
+    let systems = names
+      .iter()
+      .map(|name| {
+        builtin(name.as_ref()).ok_or_else(|| {
+          ConfigError::Invalid(format!("unknown system {}", name.as_ref()))
+        })
+      })
+      .collect::<Result<_, _>>()?;
+    Ok(Self { systems })
+  }
+
+  pub fn names(&self) -> impl Iterator<Item = &str> {
+    self.systems.iter().map(|s| s.name())
+  }
+
+  pub fn position(&self, name: &str) -> Option<usize> {
+    self.systems.iter().position(|s| s.name() == name)
+  }
+
+  pub fn push(&mut self, system: Box<dyn System>) {
+    self.systems.push(system);
+  }
+
+  pub fn insert(&mut self, index: usize, system: Box<dyn System>) {
+    self.systems.insert(index, system);
+  }
+
+  pub fn remove(&mut self, name: &str) -> Option<Box<dyn System>> {
+    let index = self.position(name)?;
+    Some(self.systems.remove(index))
+  }
+
+  pub fn replace(
+    &mut self,
+    name: &str,
+    system: Box<dyn System>,
+  ) -> Option<Box<dyn System>> {
+    let index = self.position(name)?;
+    Some(std::mem::replace(&mut self.systems[index], system))
+  }
+
+  pub fn run(&mut self, state: &mut State) {
+    for system in self.systems.iter_mut() {
+      system.run(state);
+    }
+  }
+}
@@ -0,0 +1,82 @@
+use super::System;
+use crate::person::{Observed, ResourceProbability};
+use crate::provenance::PackedProvenance;
+use crate::resource::Resource;
+use crate::state::State;
+
+/// Moves everyone a step towards their most favorable cell, or consumes what
+/// they're standing on once they're there. People who spent their move
+/// talking stay where they are.
+pub struct Movement;
+
+impl System for Movement {
+  fn name(&self) -> &str {
+    "movement"
+  }
+
+  fn run(&mut self, state: &mut State) {
+    let State {
+      config,
+      map,
+      people,
+      tick,
+      ..
+    } = state;
+
+    for person in people.iter_mut() {
+      if person.talking {
+        person.talking = false;
+        continue;
+      }
+
+      let favorability = person.favorability_map(&config.needs);
+      let dest = favorability
+        .indexed_iter()
+        .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+        .unwrap()
+        .0;
+      let dx = dest.0 as isize - person.x as isize;
+      let dy = dest.1 as isize - person.y as isize;
+
+      if dx == 0 && dy == 0 {
+        let consumed_cell =
+          map.resources.get_mut((person.x, person.y)).unwrap();
+        match consumed_cell {
+          Resource::None => {}
+          Resource::Food => {
+            if person.needs.hunger >= config.needs.hunger_per_food {
+              person.needs.hunger -= config.needs.hunger_per_food;
+              *consumed_cell = Resource::None;
+              person.brain.beliefs.set(
+                (person.x, person.y),
+                &ResourceProbability::probable(Resource::None, 1.0),
+              );
+              person.brain.observed[(person.x, person.y)] = Observed::at(*tick);
+              person.brain.provenance[(person.x, person.y)] =
+                PackedProvenance::FIRST_HAND;
+            }
+          }
+          Resource::Water => {
+            if person.needs.thirst >= config.needs.thirst_per_water {
+              person.needs.thirst -= config.needs.thirst_per_water;
+              *consumed_cell = Resource::None;
+              person.brain.beliefs.set(
+                (person.x, person.y),
+                &ResourceProbability::probable(Resource::None, 1.0),
+              );
+              person.brain.observed[(person.x, person.y)] = Observed::at(*tick);
+              person.brain.provenance[(person.x, person.y)] =
+                PackedProvenance::FIRST_HAND;
+            }
+          }
+          Resource::Stone => {}
+          Resource::Ghost => {}
+        }
+      } else if dx.abs() > dy.abs() {
+        person.x = (person.x as isize + dx.signum()) as usize;
+      } else {
+        person.y = (person.y as isize + dy.signum()) as usize;
+      }
+    }
+  }
+}
@@ -0,0 +1,22 @@
+use super::System;
+use crate::state::State;
+
+/// Makes everyone hungrier and thirstier, removing those whose needs are no
+/// longer met.
+pub struct NeedsIncrease;
+
+impl System for NeedsIncrease {
+  fn name(&self) -> &str {
+    "needs"
+  }
+
+  fn run(&mut self, state: &mut State) {
+    for person in state.people.iter_mut() {
+      person.needs.hunger += 1;
+      person.needs.thirst += 1;
+    }
+
+    let config = &state.config;
+    state.people.retain(|p| p.needs.met(&config.needs));
+  }
+}
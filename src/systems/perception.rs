@@ -0,0 +1,110 @@
+use rand::prelude::*;
+
+use super::System;
+use crate::config::{DecayModel, PerceptionUpdate};
+use crate::person::Observed;
+use crate::resource::Resource;
+use crate::state::State;
+
+/// Has everyone sense the cells within their sense range, more reliably the
+/// closer they are, and update their beliefs about them, as well as what they
+/// expect of each biome if beliefs decay towards that.
+pub struct Perception;
+
+impl System for Perception {
+  fn name(&self) -> &str {
+    "perception"
+  }
+
+  fn run(&mut self, state: &mut State) {
+    let State {
+      config,
+      map,
+      people,
+      rng,
+      tick,
+      ..
+    } = state;
+
+    for person in people.iter_mut() {
+      let sense_range = config.perception.sense_range;
+
+      let min_x = person.x.saturating_sub(sense_range);
+      let max_x = (person.x + sense_range).min(map.width());
+
+      let min_y = person.y.saturating_sub(sense_range);
+      let max_y = (person.y + sense_range).min(map.height());
+
+      let communication = &config.communication;
+      let learn_prior = config.memory.model == DecayModel::BiomePrior;
+      let trust = &mut person.trust;
+      let brain = &mut person.brain;
+
+      for x in min_x..max_x {
+        for y in min_y..max_y {
+          let m = map.resources[(x, y)];
+
+          let dist = ((person.x as f64 - x as f64).powi(2)
+            + (person.y as f64 - y as f64).powi(2))
+          .sqrt();
+
+          let certainty =
+            (sense_range as f64 - dist).max(0.0) / sense_range as f64;
+
+          let sensed = match config.perception.update {
+            PerceptionUpdate::Heuristic => {
+              brain.beliefs.update((x, y), |b| b.perceive(m, certainty));
+              m
+            }
+            PerceptionUpdate::Bayesian if certainty > 0.0 => {
+              let chance = 1.0 / Resource::variant_count() as f64;
+              let accuracy =
+                chance + (config.perception.accuracy - chance) * certainty;
+              let sensed = sense(rng, m, accuracy);
+              brain
+                .beliefs
+                .update((x, y), |b| b.observe(sensed, accuracy));
+              sensed
+            }
+            PerceptionUpdate::Bayesian => m,
+          };
+
+          if certainty > 0.0 {
+            brain.observed[(x, y)] = Observed::at(*tick);
+            brain.provenance[(x, y)]
+              .update(|p| p.perceived(certainty * certainty));
+            if learn_prior {
+              brain
+                .prior
+                .learn(map.biome(x, y), sensed, certainty * certainty);
+            }
+          }
+
+          if certainty > 0.0 && communication.trust_learning_rate > 0.0 {
+            trust.verify(
+              x,
+              y,
+              sensed,
+              communication.trust_learning_rate * certainty,
+              communication.trust,
+            );
+          }
+        }
+      }
+    }
+  }
+}
+
+/// Senses `resource` correctly with probability `accuracy`, otherwise senses
+/// one of the other resources picked at random.
+fn sense<R: Rng>(rng: &mut R, resource: Resource, accuracy: f64) -> Resource {
+  if rng.gen_bool(accuracy) {
+    resource
+  } else {
+    *Resource::variants()
+      .iter()
+      .filter(|r| **r != resource)
+      .choose(rng)
+      .unwrap()
+  }
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid::{Grid, Layout};
+use crate::resource::Resource;
+
+/// How much someone trusts each person they've talked to, learned from how
+/// often what they were told matched what they later saw for themselves.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Trust {
+  scores: BTreeMap<usize, f64>,
+  /// The last unchecked claim about each cell, only kept while learning.
+  claims: Option<Grid<Option<Claim>>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Claim {
+  pub from: usize,
+  pub resource: Resource,
+}
+
+impl Trust {
+  /// Trust that's only ever `initial` unless `learning`, in which case claims
+  /// are kept to check against what's seen.
+  pub fn new(
+    width: usize,
+    height: usize,
+    layout: Layout,
+    learning: bool,
+  ) -> Self {
+    Self {
+      scores: BTreeMap::new(),
+      claims: learning.then(|| Grid::from_elem((width, height), None, layout)),
+    }
+  }
+
+  /// Trust in a person, `initial` if it hasn't been learned yet.
+  pub fn of(&self, id: usize, initial: f64) -> f64 {
+    self.scores.get(&id).copied().unwrap_or(initial)
+  }
+
+  pub fn scores(&self) -> impl Iterator<Item = (usize, f64)> + '_ {
+    self.scores.iter().map(|(id, score)| (*id, *score))
+  }
+
+  pub fn claim(&self, x: usize, y: usize) -> Option<Claim> {
+    self.claims.as_ref().and_then(|claims| claims[(x, y)])
+  }
+
+  /// Remembers a claim to check later, if learning.
+  pub fn record_claim(&mut self, x: usize, y: usize, claim: Claim) {
+    if let Some(claims) = &mut self.claims {
+      claims[(x, y)] = Some(claim);
+    }
+  }
+
+  /// Checks the claim about a cell, if any, against what was seen there and
+  /// moves trust in whoever made it towards 1 if it was right or 0 if it was
+  /// wrong. The claim is forgotten once checked.
+  pub fn verify(
+    &mut self,
+    x: usize,
+    y: usize,
+    seen: Resource,
+    rate: f64,
+    initial: f64,
+  ) {
+    let Some(claims) = &mut self.claims else {
+      return;
+    };
+    if let Some(claim) = claims[(x, y)] {
+      claims.clear((x, y));
+      let outcome = if claim.resource == seen { 1.0 } else { 0.0 };
+      let score = self.scores.entry(claim.from).or_insert(initial);
+      *score += rate * (outcome - *score);
+    }
+  }
+}
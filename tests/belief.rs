@@ -0,0 +1,98 @@
+use info_distribution::belief::{
+  LastSeen, Packed, Probability32, Probability8,
+};
+use info_distribution::{Belief, Resource, ResourceProbability};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+fn distance(a: &ResourceProbability, b: &ResourceProbability) -> f64 {
+  let (a, b) = (a.clone().into_inner(), b.clone().into_inner());
+  a.iter()
+    .zip(b)
+    .map(|(a, b)| (a - b).abs())
+    .fold(0.0, f64::max)
+}
+
+fn resource<R: Rng>(rng: &mut R) -> Resource {
+  let ordinal = rng.gen_range(0..Resource::variant_count());
+  Resource::from_ordinal(ordinal as i8).unwrap()
+}
+
+/// Packs random distributions and runs the same updates on the packed and
+/// double precision beliefs, which should never drift further than
+/// `tolerance` apart.
+fn packed_tracks_f64<P: Packed + Belief>(tolerance: f64) {
+  let mut rng = StdRng::seed_from_u64(4);
+  for _ in 0..100 {
+    let start = ResourceProbability::gen(&mut rng);
+    let mut packed = P::pack(&start);
+    assert!(distance(&packed.unpack(), &start) <= tolerance);
+
+    let mut exact = start;
+    for _ in 0..20 {
+      match rng.gen_range(0..3) {
+        0 => {
+          let sensed = resource(&mut rng);
+          let certainty = rng.gen();
+          packed.perceive(sensed, certainty);
+          exact.perceive(sensed, certainty);
+        }
+        1 => {
+          let other = ResourceProbability::gen(&mut rng);
+          let trust = rng.gen();
+          packed.merge(&other, None, trust);
+          exact.merge(&other, None, trust);
+        }
+        _ => {
+          let percent = rng.gen_range(1.0 / 255.0..0.1);
+          packed.decay(percent);
+          exact.decay(percent);
+        }
+      }
+      assert!(
+        distance(&packed.unpack(), &exact) <= tolerance,
+        "{:?} drifted from {:?}",
+        packed.unpack().into_inner(),
+        exact.into_inner()
+      );
+    }
+  }
+}
+
+#[test]
+fn f32_beliefs_track_f64() {
+  packed_tracks_f64::<Probability32>(1e-6);
+}
+
+#[test]
+fn u8_beliefs_track_f64() {
+  packed_tracks_f64::<Probability8>(0.02);
+}
+
+#[test]
+fn last_seen_takes_the_latest_sighting() {
+  let mut belief = LastSeen::from_distribution(&ResourceProbability::even());
+  belief.perceive(Resource::Food, 0.9);
+  belief.perceive(Resource::Water, 0.2);
+  assert_eq!(belief.most_likely(), Resource::Water);
+  let seen = ResourceProbability::probable(Resource::Water, 0.2);
+  assert!(
+    (belief.probability(Resource::Water) - seen.get(Resource::Water)).abs()
+      < 1e-9
+  );
+
+  // Out of sight isn't a sighting.
+  belief.perceive(Resource::Stone, 0.0);
+  assert_eq!(belief.most_likely(), Resource::Water);
+}
+
+#[test]
+fn last_seen_perceives_as_it_observes() {
+  let mut perceived = LastSeen::from_distribution(&ResourceProbability::even());
+  let mut observed = perceived;
+  perceived.perceive(Resource::Food, 0.9);
+  perceived.perceive(Resource::Water, 0.3);
+  observed.observe(Resource::Food, 0.9);
+  observed.observe(Resource::Water, 0.3);
+  assert_eq!(perceived.most_likely(), observed.most_likely());
+}
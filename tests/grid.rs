@@ -0,0 +1,109 @@
+use info_distribution::grid::{Grid, Layout};
+use ndarray::Array2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const DIM: (usize, usize) = (7, 5);
+
+fn layouts() -> [Layout; 4] {
+  [
+    Layout::Dense,
+    Layout::Sparse,
+    Layout::Tiled { size: 3 },
+    Layout::Tiled { size: 8 },
+  ]
+}
+
+fn cell<R: Rng>(rng: &mut R) -> (usize, usize) {
+  (rng.gen_range(0..DIM.0), rng.gen_range(0..DIM.1))
+}
+
+#[test]
+fn every_layout_reads_back_what_was_written() {
+  for layout in layouts() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut grid = Grid::from_elem(DIM, 0u8, layout);
+    let mut expected = Array2::from_elem(DIM, 0u8);
+    for _ in 0..500 {
+      let i = cell(&mut rng);
+      let value = rng.gen_range(0..4);
+      match rng.gen_range(0..4) {
+        0 => grid.set(i, value),
+        1 => grid.update(i, |v| *v = value),
+        2 => grid[i] = value,
+        _ => {
+          grid.clear(i);
+          expected[i] = 0;
+          continue;
+        }
+      }
+      expected[i] = value;
+    }
+
+    for (i, value) in expected.indexed_iter() {
+      assert_eq!(grid[i], *value, "{layout:?} at {i:?}");
+      assert_eq!(grid.is_default(i), *value == 0, "{layout:?} at {i:?}");
+    }
+    assert_eq!(grid.to_array(|v| *v), expected, "{layout:?}");
+    assert_eq!(
+      grid.map(|v| *v as u32 * 2).to_array(|v| *v as u8 / 2),
+      expected
+    );
+    let mut visited = Array2::from_elem(DIM, u8::MAX);
+    grid.for_each(|i, v| visited[i] = *v);
+    assert_eq!(visited, expected, "{layout:?}");
+  }
+}
+
+#[test]
+fn every_layout_survives_a_round_trip() {
+  for layout in layouts() {
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut grid = Grid::from_elem(DIM, 0u8, layout);
+    for _ in 0..20 {
+      grid.set(cell(&mut rng), rng.gen_range(1..4));
+    }
+    let bytes = bincode::serialize(&grid).unwrap();
+    let read: Grid<u8> = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(read.to_array(|v| *v), grid.to_array(|v| *v), "{layout:?}");
+    assert_eq!(read.stored(), grid.stored(), "{layout:?}");
+  }
+}
+
+#[test]
+fn setting_the_default_stores_nothing() {
+  for layout in [Layout::Sparse, Layout::Tiled { size: 3 }] {
+    let mut grid = Grid::from_elem(DIM, 0u8, layout);
+    grid.for_each_mut(|_, v| *v = 0);
+    grid.set((1, 1), 0);
+    grid.update((2, 2), |v| *v = 0);
+    assert_eq!(grid.stored(), 0, "{layout:?}");
+
+    grid.set((1, 1), 1);
+    assert!(grid.stored() > 0, "{layout:?}");
+    grid.set((1, 1), 0);
+    if layout == Layout::Sparse {
+      assert_eq!(grid.stored(), 0);
+    }
+  }
+}
+
+#[test]
+fn stored_cells_are_updated_alike_in_every_layout() {
+  let mut grids = layouts().map(|layout| {
+    let mut rng = StdRng::seed_from_u64(3);
+    let mut grid = Grid::from_elem(DIM, 0u8, layout);
+    for _ in 0..20 {
+      grid.set(cell(&mut rng), rng.gen_range(1..4));
+    }
+    grid
+  });
+  for grid in grids.iter_mut() {
+    grid.for_each_stored_mut(|_, v| *v -= 1);
+    grid.for_each_mut(|(x, y), v| *v += (x + y) as u8 % 2);
+  }
+  let dense = grids[0].to_array(|v| *v);
+  for grid in grids.iter() {
+    assert_eq!(grid.to_array(|v| *v), dense);
+  }
+}
@@ -0,0 +1,32 @@
+use info_distribution::{SimConfig, State};
+
+#[test]
+fn selecting_people_after_everyone_died_does_nothing() {
+  let mut config = SimConfig::default();
+  config.map.width = 20;
+  config.map.height = 20;
+  config.needs.max_hunger = 1;
+  let mut state = State::gen(config, 1).unwrap();
+
+  while !state.people().is_empty() {
+    state.update();
+  }
+
+  state.select_next_person();
+  state.select_previous_person();
+  assert!(state.selected_person().is_none());
+}
+
+#[test]
+fn selection_wraps_around() {
+  let mut config = SimConfig::default();
+  config.map.width = 20;
+  config.map.height = 20;
+  config.people.count = 3;
+  let mut state = State::gen(config, 1).unwrap();
+
+  state.select_previous_person();
+  assert_eq!(state.selected_person().map(|p| p.id()), Some(2));
+  state.select_next_person();
+  assert_eq!(state.selected_person().map(|p| p.id()), Some(0));
+}
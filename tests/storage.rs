@@ -0,0 +1,86 @@
+use info_distribution::config::{
+  BeliefMix, DecayModel, InitialBeliefs, Storage,
+};
+use info_distribution::metrics::TickMetrics;
+use info_distribution::{SimConfig, State};
+
+fn config(storage: Storage, initial: InitialBeliefs) -> SimConfig {
+  let mut config = SimConfig::default();
+  config.map.width = 30;
+  config.map.height = 30;
+  config.people.beliefs = BeliefMix {
+    probability: 1.0,
+    last_seen: 1.0,
+    dirichlet: 1.0,
+  };
+  config.memory.storage = storage;
+  config.memory.tile_size = 8;
+  config.memory.initial = initial;
+  config
+}
+
+/// Metrics of every tick of a short run, as JSON so they compare exactly.
+fn run(config: SimConfig) -> Vec<String> {
+  let mut state = State::gen(config, 3).unwrap();
+  (0..10)
+    .map(|_| {
+      state.update();
+      serde_json::to_string(&TickMetrics::collect(&state)).unwrap()
+    })
+    .collect()
+}
+
+#[test]
+fn storage_does_not_change_the_simulation() {
+  for initial in [InitialBeliefs::Guess, InitialBeliefs::Even] {
+    for model in [DecayModel::Uniform, DecayModel::Capacity] {
+      let with = |storage| {
+        let mut config = config(storage, initial);
+        // A rate at which decaying an even belief doesn't leave it exactly
+        // even in floating point.
+        config.memory.decay = 0.0578;
+        config.memory.model = model;
+        config.memory.capacity = 200;
+        run(config)
+      };
+      let dense = with(Storage::Dense);
+      assert!(
+        dense == with(Storage::Sparse),
+        "sparse differs with {:?} {:?}",
+        initial,
+        model
+      );
+      assert!(
+        dense == with(Storage::Tiled),
+        "tiled differs with {:?} {:?}",
+        initial,
+        model
+      );
+    }
+  }
+}
+
+#[test]
+fn sparse_brains_stay_sparse() {
+  let mut config = config(Storage::Sparse, InitialBeliefs::Even);
+  config.map.width = 100;
+  config.map.height = 100;
+  config.people.count = 6;
+  let mut state = State::gen(config, 1).unwrap();
+
+  for _ in 0..3 {
+    state.update();
+  }
+
+  // Everything kept about a person's surroundings and what they've heard
+  // should still take less than a single dense grid.
+  for person in state.people() {
+    let brain = person.brain();
+    assert!(
+      brain.stored() < brain.width() * brain.height(),
+      "person {} stores {} cells",
+      person.id(),
+      brain.stored()
+    );
+  }
+}